@@ -0,0 +1,412 @@
+// Portable agent bundles: a tar.gz of what `get_current_config` reads from
+// ~/.openclaw (openclaw.json, clawnetes-meta.json, every agent's workspace with its
+// custom skills, and auth profiles), for moving an agent to another machine.
+// Bundles never carry secrets: tokens, keys and passwords, and every credential in
+// auth profiles, are blanked on export and listed in the manifest, so they can be
+// entered again on the machine imported to.
+
+use crate::config_plan::{blank_auth_credentials, is_auth_profiles, is_secret_key, ConfigTarget, AUTH_PROFILE_KEYS};
+use crate::snapshot::{self, run_sh, SNAPSHOT_PATHS};
+use crate::{gateway_token_from_config, random_gateway_token, shell_quote, MODE_PRIVATE, MODE_PUBLIC};
+use std::io::Read;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const BUNDLE_VERSION: u32 = 1;
+const MANIFEST: &str = "bundle.json";
+// Files from ~/.openclaw are stored under this directory in the archive
+const FILES_DIR: &str = "openclaw";
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct BundleManifest {
+    pub(crate) version: u32,
+    // Seconds since the Unix epoch
+    pub(crate) created_at: u64,
+    // Home directory of the machine exported from, for re-homing absolute paths
+    pub(crate) source_home: String,
+    // Paths relative to ~/.openclaw
+    pub(crate) files: Vec<String>,
+    // Secrets blanked on export, as "<file>: <dotted key path>"
+    pub(crate) stripped_secrets: Vec<String>,
+}
+
+#[derive(serde::Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ImportReport {
+    pub(crate) files: Vec<String>,
+    // Secrets the bundle left out that the imported agent still needs
+    pub(crate) missing_secrets: Vec<String>,
+    // Snapshot of ~/.openclaw taken before importing, to roll back to
+    pub(crate) snapshot: Option<String>,
+}
+
+/// `name` as a path under ~/.openclaw when it is one a bundle may hold: openclaw.json,
+/// clawnetes-meta.json, or a file in a workspace or an agent directory. Anything
+/// else, including paths that climb out with "..", is refused.
+pub(crate) fn bundle_path(name: &str) -> Option<String> {
+    let parts: Vec<&str> = name.trim_start_matches("./").split('/').collect();
+    if parts.iter().any(|part| part.is_empty() || *part == "." || *part == ".." || part.contains('\\')) {
+        return None;
+    }
+    let allowed = matches!(
+        parts.as_slice(),
+        ["openclaw.json"] | ["clawnetes-meta.json"] | ["workspace", _, ..] | ["agents", _, "workspace" | "agent", _, ..]
+    );
+    allowed.then(|| parts.join("/"))
+}
+
+/// Blank every secret-looking string in `value`, recording where each one was.
+fn strip_secrets(value: &mut serde_json::Value, path: &str, stripped: &mut Vec<String>) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                let key_path = if path.is_empty() { key.clone() } else { format!("{}.{}", path, key) };
+                match value {
+                    serde_json::Value::String(secret) if is_secret_key(key) => {
+                        if !secret.is_empty() {
+                            secret.clear();
+                            stripped.push(key_path);
+                        }
+                    }
+                    _ => strip_secrets(value, &key_path, stripped),
+                }
+            }
+        }
+        serde_json::Value::Array(items) => {
+            for (i, item) in items.iter_mut().enumerate() {
+                strip_secrets(item, &format!("{}.{}", path, i), stripped);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Point absolute paths under `from` (a home directory) at `to` instead.
+fn rehome(value: &mut serde_json::Value, from: &str, to: &str) {
+    match value {
+        serde_json::Value::String(s) => {
+            if let Some(rest) = s.strip_prefix(from) {
+                if rest.is_empty() || rest.starts_with('/') {
+                    *s = format!("{}{}", to, rest);
+                }
+            }
+        }
+        serde_json::Value::Object(map) => map.values_mut().for_each(|value| rehome(value, from, to)),
+        serde_json::Value::Array(items) => items.iter_mut().for_each(|value| rehome(value, from, to)),
+        _ => {}
+    }
+}
+
+/// Remove Telegram accounts whose bot token was stripped, so the gateway does not start
+/// a channel that cannot connect, and turn the plugin off once none are left.
+fn drop_tokenless_telegram(config: &mut serde_json::Value) {
+    let Some(channels) = config.get_mut("channels").and_then(|channels| channels.as_object_mut()) else { return };
+    let Some(accounts) = channels.get_mut("telegram").and_then(|telegram| telegram.get_mut("accounts")).and_then(|a| a.as_object_mut())
+    else {
+        return;
+    };
+    accounts.retain(|_, account| account.get("botToken").and_then(|token| token.as_str()) != Some(""));
+    if !accounts.is_empty() {
+        return;
+    }
+    channels.remove("telegram");
+    if channels.is_empty() {
+        if let Some(config) = config.as_object_mut() {
+            config.remove("channels");
+        }
+    }
+    if let Some(plugin) = config.pointer_mut("/plugins/entries/telegram").and_then(|plugin| plugin.as_object_mut()) {
+        plugin.insert("enabled".to_string(), serde_json::Value::Bool(false));
+    }
+}
+
+/// Put the target's own credentials back into an imported auth-profiles.json `value`:
+/// profiles the target has with credentials set are kept as they are there, and so are
+/// profiles only the target has. Returns the names of the profiles taken from the target.
+fn keep_target_credentials(value: &mut serde_json::Value, existing: &serde_json::Value) -> Vec<String> {
+    let mut kept = Vec::new();
+    let Some(existing) = existing.get("profiles").and_then(|profiles| profiles.as_object()) else { return kept };
+    let Some(profiles) = value.get_mut("profiles").and_then(|profiles| profiles.as_object_mut()) else { return kept };
+    for (name, current) in existing {
+        let has_credentials = current.as_object().is_some_and(|current| {
+            current.iter().any(|(key, field)| {
+                !AUTH_PROFILE_KEYS.contains(&key.as_str()) && field.as_str().is_some_and(|s| !s.is_empty())
+            })
+        });
+        if has_credentials || !profiles.contains_key(name) {
+            profiles.insert(name.clone(), current.clone());
+            kept.push(name.clone());
+        }
+    }
+    kept
+}
+
+fn pretty(value: &serde_json::Value) -> Result<String, String> {
+    serde_json::to_string_pretty(value).map_err(|e| e.to_string())
+}
+
+/// Bundle the agent configured on `target`. Only markdown and JSON files are taken:
+/// that is everything Clawnetes writes, and it leaves session logs and caches behind.
+pub(crate) fn export(target: &dyn ConfigTarget, home: &str) -> Result<(Vec<u8>, BundleManifest), String> {
+    let root = format!("{}/.openclaw", home);
+    let listing = run_sh(
+        target,
+        &format!(
+            "cd {} 2>/dev/null && find {} -type f \\( -name '*.md' -o -name '*.json' \\) 2>/dev/null; true",
+            shell_quote(&root),
+            SNAPSHOT_PATHS
+        ),
+    )?;
+    let mut files: Vec<String> = listing.lines().filter_map(bundle_path).collect();
+    files.sort();
+    if !files.iter().any(|file| file == "openclaw.json") {
+        return Err("Nothing to export: openclaw.json was not found".to_string());
+    }
+
+    let mut archived = Vec::new();
+    let mut stripped_secrets = Vec::new();
+    let mut archive = tar::Builder::new(flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default()));
+    for file in &files {
+        let mut contents = target.read(&format!("{}/{}", root, file))?;
+        if file.ends_with(".json") {
+            // Files that are not valid JSON cannot be searched for secrets, so they stay behind
+            let Ok(mut value) = serde_json::from_str::<serde_json::Value>(&contents) else { continue };
+            let stripped = if is_auth_profiles(file) {
+                blank_auth_credentials(&mut value, "")
+            } else {
+                let mut stripped = Vec::new();
+                strip_secrets(&mut value, "", &mut stripped);
+                stripped
+            };
+            stripped_secrets.extend(stripped.into_iter().map(|key| format!("{}: {}", file, key)));
+            contents = pretty(&value)?;
+        }
+        append(&mut archive, &format!("{}/{}", FILES_DIR, file), contents.as_bytes())?;
+        archived.push(file.clone());
+    }
+
+    let manifest = BundleManifest {
+        version: BUNDLE_VERSION,
+        created_at: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+        source_home: home.to_string(),
+        files: archived,
+        stripped_secrets,
+    };
+    append(&mut archive, MANIFEST, pretty(&serde_json::to_value(&manifest).map_err(|e| e.to_string())?)?.as_bytes())?;
+    let bytes = archive.into_inner().and_then(|gz| gz.finish()).map_err(|e| format!("Failed to write bundle: {}", e))?;
+    Ok((bytes, manifest))
+}
+
+fn append<W: std::io::Write>(archive: &mut tar::Builder<W>, path: &str, data: &[u8]) -> Result<(), String> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    archive.append_data(&mut header, path, data).map_err(|e| format!("Failed to write bundle: {}", e))
+}
+
+/// Unpack a bundle onto `target`, replacing the files it holds and leaving others
+/// alone. Absolute paths from the exporting machine are re-homed to `home`, and the
+/// target keeps its own gateway token so paired devices stay paired, and its own
+/// credentials in auth profiles it already has. ~/.openclaw is snapshotted first.
+pub(crate) fn import(target: &dyn ConfigTarget, home: &str, bundle: &[u8]) -> Result<ImportReport, String> {
+    let mut manifest: Option<BundleManifest> = None;
+    let mut files = Vec::new();
+    let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(bundle));
+    for entry in archive.entries().map_err(|e| format!("Not a Clawnetes bundle: {}", e))? {
+        let mut entry = entry.map_err(|e| format!("Not a Clawnetes bundle: {}", e))?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let name = entry.path().map_err(|e| e.to_string())?.to_string_lossy().to_string();
+        let mut contents = String::new();
+        entry.read_to_string(&mut contents).map_err(|e| format!("Failed to read {} from bundle: {}", name, e))?;
+        if name == MANIFEST {
+            manifest = Some(serde_json::from_str(&contents).map_err(|e| format!("Invalid bundle manifest: {}", e))?);
+        } else {
+            let path = name.strip_prefix(&format!("{}/", FILES_DIR)).and_then(bundle_path);
+            files.push((path.ok_or_else(|| format!("Unexpected file in bundle: {}", name))?, contents));
+        }
+    }
+    let manifest = manifest.ok_or("Not a Clawnetes bundle: bundle.json is missing")?;
+    if manifest.version != BUNDLE_VERSION {
+        return Err(format!("Unsupported bundle version {}", manifest.version));
+    }
+
+    let root = format!("{}/.openclaw", home);
+    let existing_token = serde_json::from_str(&target.read(&format!("{}/openclaw.json", root))?)
+        .ok()
+        .and_then(|existing| gateway_token_from_config(&existing));
+    let snapshot = snapshot::take(target, home, "import")?.map(|s| s.id);
+
+    // Secrets the target already has, as "<file>: profiles.<name>" prefixes of stripped secrets
+    let mut kept_secrets = Vec::new();
+    for (path, contents) in &mut files {
+        if path.ends_with(".json") {
+            let mut value: serde_json::Value =
+                serde_json::from_str(contents).map_err(|e| format!("Invalid {} in bundle: {}", path, e))?;
+            rehome(&mut value, &manifest.source_home, home);
+            if is_auth_profiles(path) {
+                let existing = serde_json::from_str(&target.read(&format!("{}/{}", root, path))?).unwrap_or_default();
+                let kept = keep_target_credentials(&mut value, &existing);
+                kept_secrets.extend(kept.into_iter().map(|name| format!("{}: profiles.{}", path, name)));
+            }
+            if path == "openclaw.json" {
+                let token = existing_token.clone().unwrap_or_else(random_gateway_token);
+                if let Some(auth) = value.pointer_mut("/gateway/auth").and_then(|auth| auth.as_object_mut()) {
+                    auth.insert("token".to_string(), serde_json::Value::String(token));
+                }
+                drop_tokenless_telegram(&mut value);
+            }
+            *contents = pretty(&value)?;
+        }
+        if let Some((dir, _)) = path.rsplit_once('/') {
+            target.mkdir_p(&format!("{}/{}", root, dir))?;
+        }
+        // openclaw.json and agent directories hold credentials once secrets are re-entered
+        let private = path == "openclaw.json" || path.split('/').nth(2) == Some("agent");
+        target.write(&format!("{}/{}", root, path), contents, if private { MODE_PRIVATE } else { MODE_PUBLIC })?;
+    }
+
+    // The gateway token and kept credentials were filled in from the target, so they are not missing
+    let missing_secrets = manifest
+        .stripped_secrets
+        .into_iter()
+        .filter(|secret| secret != "openclaw.json: gateway.auth.token")
+        .filter(|secret| {
+            !kept_secrets.iter().any(|kept| secret == kept || secret.starts_with(&format!("{}.", kept)))
+        })
+        .collect();
+    Ok(ImportReport { files: files.into_iter().map(|(path, _)| path).collect(), missing_secrets, snapshot })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{ShellTarget, TempDir};
+    use std::fs;
+
+    #[test]
+    fn test_bundle_paths_stay_inside_openclaw() {
+        assert_eq!(bundle_path("./openclaw.json").as_deref(), Some("openclaw.json"));
+        assert_eq!(bundle_path("workspace/skills/notes/SKILL.md").as_deref(), Some("workspace/skills/notes/SKILL.md"));
+        assert_eq!(bundle_path("agents/research/agent/auth-profiles.json").as_deref(), Some("agents/research/agent/auth-profiles.json"));
+        for refused in ["workspace", "agents/research/sessions/log.json", "workspace/../../.ssh/id_ed25519", "/etc/passwd", "workspace//x.md", "credentials.json"] {
+            assert_eq!(bundle_path(refused), None, "{} is refused", refused);
+        }
+    }
+
+    #[test]
+    fn test_agent_bundle_moves_between_homes_without_secrets() {
+        let laptop_dir = TempDir::new("bundle_from");
+        let server_dir = TempDir::new("bundle_to");
+        let (laptop, server) = (laptop_dir.as_str(), server_dir.as_str());
+
+        let root = laptop_dir.join(".openclaw");
+        fs::create_dir_all(root.join("workspace/skills/notes")).unwrap();
+        fs::create_dir_all(root.join("agents/research/agent")).unwrap();
+        fs::create_dir_all(root.join("agents/research/sessions")).unwrap();
+        let openclaw_json = serde_json::json!({
+            "agents": { "list": [{
+                "id": "research",
+                "workspace": format!("{}/.openclaw/agents/research/workspace", laptop),
+                "agentDir": format!("{}/.openclaw/agents/research/agent", laptop),
+            }] },
+            "gateway": { "auth": { "mode": "token", "token": "laptop-gateway-token" } },
+            "channels": { "telegram": { "accounts": { "default": { "botToken": "123:telegram-secret" } } } },
+            "plugins": { "entries": { "telegram": { "enabled": true } } },
+        });
+        fs::write(root.join("openclaw.json"), openclaw_json.to_string()).unwrap();
+        fs::write(root.join("clawnetes-meta.json"), r#"{"agent_type":"research"}"#).unwrap();
+        fs::write(root.join("workspace/SOUL.md"), "be kind\n").unwrap();
+        fs::write(root.join("workspace/skills/notes/SKILL.md"), "# Notes\n").unwrap();
+        let auth_profiles = serde_json::json!({
+            "version": 1,
+            "profiles": {
+                "anthropic:default": { "type": "token", "provider": "anthropic", "token": "sk-secret" },
+                // OAuth credentials go by names no key-name guess would catch
+                "openai-codex:default": { "type": "oauth", "provider": "openai-codex", "access": "oauth-access", "refresh": "oauth-refresh" }
+            },
+            "lastGood": { "anthropic": "anthropic:default" },
+            "usageStats": { "anthropic:default": { "lastUsed": 1700000000 } }
+        });
+        fs::write(root.join("agents/research/agent/auth-profiles.json"), auth_profiles.to_string()).unwrap();
+        fs::write(root.join("agents/research/agent/models.json"), "{ truncated").unwrap();
+        fs::write(root.join("agents/research/sessions/s1.json"), "{}").unwrap();
+
+        let (bytes, manifest) = export(&ShellTarget, laptop).unwrap();
+        assert_eq!(
+            manifest.files,
+            [
+                "agents/research/agent/auth-profiles.json",
+                "clawnetes-meta.json",
+                "openclaw.json",
+                "workspace/SOUL.md",
+                "workspace/skills/notes/SKILL.md",
+            ]
+        );
+        assert_eq!(
+            manifest.stripped_secrets,
+            [
+                "agents/research/agent/auth-profiles.json: profiles.anthropic:default.token",
+                "agents/research/agent/auth-profiles.json: profiles.openai-codex:default.access",
+                "agents/research/agent/auth-profiles.json: profiles.openai-codex:default.refresh",
+                "openclaw.json: gateway.auth.token",
+                "openclaw.json: channels.telegram.accounts.default.botToken",
+            ]
+        );
+        let mut unpacked = Vec::new();
+        flate2::read::GzDecoder::new(bytes.as_slice()).read_to_end(&mut unpacked).unwrap();
+        let unpacked = String::from_utf8_lossy(&unpacked);
+        for secret in ["laptop-gateway-token", "telegram-secret", "sk-secret", "oauth-access", "oauth-refresh"] {
+            assert!(!unpacked.contains(secret), "{} is left out of the bundle", secret);
+        }
+
+        // The server already runs a gateway, whose token its paired devices know
+        let server_root = server_dir.join(".openclaw");
+        fs::create_dir_all(&server_root).unwrap();
+        fs::write(server_root.join("openclaw.json"), r#"{"gateway":{"auth":{"token":"server-gateway-token"}}}"#).unwrap();
+        // It also has a working Anthropic key for the agent, and a profile of its own
+        fs::create_dir_all(server_root.join("agents/research/agent")).unwrap();
+        let server_profiles = serde_json::json!({
+            "version": 1,
+            "profiles": {
+                "anthropic:default": { "type": "token", "provider": "anthropic", "token": "server-anthropic-key" },
+                "openai-codex:default": { "type": "oauth", "provider": "openai-codex", "access": "", "refresh": "" },
+                "google:default": { "type": "token", "provider": "google", "token": "server-google-key" }
+            }
+        });
+        fs::write(server_root.join("agents/research/agent/auth-profiles.json"), server_profiles.to_string()).unwrap();
+
+        let report = import(&ShellTarget, server, &bytes).unwrap();
+        assert_eq!(report.files, manifest.files);
+        // The gateway token and the Anthropic key are the server's own, so only the others need entering again
+        assert_eq!(
+            report.missing_secrets,
+            [
+                "agents/research/agent/auth-profiles.json: profiles.openai-codex:default.access",
+                "agents/research/agent/auth-profiles.json: profiles.openai-codex:default.refresh",
+                "openclaw.json: channels.telegram.accounts.default.botToken",
+            ]
+        );
+        assert!(report.snapshot.as_deref().is_some_and(|id| id.ends_with("-import")));
+
+        let imported: serde_json::Value = serde_json::from_str(&fs::read_to_string(server_root.join("openclaw.json")).unwrap()).unwrap();
+        assert_eq!(imported["agents"]["list"][0]["workspace"], format!("{}/.openclaw/agents/research/workspace", server));
+        assert_eq!(imported["agents"]["list"][0]["agentDir"], format!("{}/.openclaw/agents/research/agent", server));
+        assert_eq!(imported["gateway"]["auth"]["token"], "server-gateway-token");
+        // A Telegram channel without its token would not connect, so it is turned off
+        assert!(imported.get("channels").is_none());
+        assert_eq!(imported["plugins"]["entries"]["telegram"]["enabled"], false);
+        let profiles: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(server_root.join("agents/research/agent/auth-profiles.json")).unwrap()).unwrap();
+        assert_eq!(profiles["profiles"]["openai-codex:default"]["type"], "oauth");
+        assert_eq!(profiles["profiles"]["anthropic:default"]["token"], "server-anthropic-key");
+        assert_eq!(profiles["profiles"]["google:default"]["token"], "server-google-key");
+        assert_eq!(profiles["lastGood"], auth_profiles["lastGood"]);
+        assert_eq!(fs::read_to_string(server_root.join("workspace/skills/notes/SKILL.md")).unwrap(), "# Notes\n");
+        assert!(!server_root.join("agents/research/sessions").exists());
+
+        assert!(import(&ShellTarget, server, b"not a bundle").is_err());
+    }
+}
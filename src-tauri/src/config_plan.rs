@@ -0,0 +1,357 @@
+// Dry runs of a configure. Both configure paths write through a `ConfigTarget`;
+// `PlanTarget` stands in for the real one, passing reads through but keeping
+// writes in memory and recording commands instead of running them, so a plan
+// comes from the same code as an apply.
+
+use crate::openclaw_config::MergeReport;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+// Lines of unchanged context around each hunk, as in `diff -u`
+const CONTEXT: usize = 3;
+const MASK: &str = "********";
+
+/// The machine being configured: the local install (or WSL), or a host over SSH.
+pub(crate) trait ConfigTarget {
+    /// The contents of `path`, or an empty string when it does not exist.
+    fn read(&self, path: &str) -> Result<String, String>;
+    /// Replace `path` with `contents`; `mode` applies where the target sets permissions.
+    fn write(&self, path: &str, contents: &str, mode: i32) -> Result<(), String>;
+    fn mkdir_p(&self, path: &str) -> Result<(), String>;
+    fn run(&self, cmd: &str) -> Result<String, String>;
+    /// Like `run`, for long commands whose output is worth showing as it arrives.
+    fn run_streaming(&self, cmd: &str) -> Result<String, String> {
+        self.run(cmd)
+    }
+    /// Run `f` as a named step of the install, unless the operation was cancelled.
+    fn step(&self, _name: &str, f: &mut dyn FnMut() -> Result<(), String>) -> Result<(), String> {
+        crate::check_cancelled()?;
+        f()
+    }
+    /// Report a line of progress.
+    fn note(&self, _line: &str) {}
+}
+
+/// A file a configure (or a snapshot restore) would write, with the change masked of secrets.
+#[derive(serde::Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PlannedFile {
+    pub(crate) path: String,
+    // "added", "modified", "removed" or "unchanged"
+    pub(crate) status: String,
+    // Unified diff against the current file; empty when unchanged
+    pub(crate) diff: String,
+}
+
+#[derive(serde::Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ConfigPlan {
+    pub(crate) files: Vec<PlannedFile>,
+    // Shell (or SSH) commands in the order they would run
+    pub(crate) commands: Vec<String>,
+    // What merging into openclaw.json would change, when merging
+    pub(crate) merge: Option<MergeReport>,
+}
+
+pub(crate) struct PlanTarget<'a> {
+    base: &'a dyn ConfigTarget,
+    // Contents written so far, by path
+    files: RefCell<BTreeMap<String, String>>,
+    commands: RefCell<Vec<String>>,
+}
+
+impl<'a> PlanTarget<'a> {
+    pub(crate) fn new(base: &'a dyn ConfigTarget) -> PlanTarget<'a> {
+        PlanTarget { base, files: RefCell::default(), commands: RefCell::default() }
+    }
+
+    /// Record a command that runs after the configure itself, e.g. skill installs.
+    pub(crate) fn then_run(&self, cmd: &str) {
+        self.commands.borrow_mut().push(cmd.to_string());
+    }
+
+    /// The plan: every written file diffed against the target's current copy, and
+    /// the commands. `secrets` are masked wherever they appear, and so is every
+    /// secret-looking value in JSON files.
+    pub(crate) fn finish(self, merge: Option<MergeReport>, secrets: &[&str]) -> Result<ConfigPlan, String> {
+        let mut files = Vec::new();
+        for (path, new) in self.files.into_inner() {
+            let old = self.base.read(&path)?;
+            let (old, new) = if path.ends_with(".json") { (mask_json(&path, &old), mask_json(&path, &new)) } else { (old, new) };
+            let (old, new) = (mask_literals(&old, secrets), mask_literals(&new, secrets));
+            let status = if old.is_empty() {
+                "added"
+            } else if old == new {
+                "unchanged"
+            } else {
+                "modified"
+            };
+            files.push(PlannedFile { diff: unified_diff(&path, &old, &new), path, status: status.to_string() });
+        }
+        let commands = self.commands.into_inner().iter().map(|cmd| mask_literals(cmd, secrets)).collect();
+        Ok(ConfigPlan { files, commands, merge })
+    }
+}
+
+impl ConfigTarget for PlanTarget<'_> {
+    fn read(&self, path: &str) -> Result<String, String> {
+        match self.files.borrow().get(path) {
+            Some(contents) => Ok(contents.clone()),
+            None => self.base.read(path),
+        }
+    }
+
+    fn write(&self, path: &str, contents: &str, _mode: i32) -> Result<(), String> {
+        self.files.borrow_mut().insert(path.to_string(), contents.to_string());
+        Ok(())
+    }
+
+    fn mkdir_p(&self, path: &str) -> Result<(), String> {
+        self.then_run(&format!("mkdir -p {}", path));
+        Ok(())
+    }
+
+    fn run(&self, cmd: &str) -> Result<String, String> {
+        self.then_run(cmd);
+        Ok(String::new())
+    }
+}
+
+pub(crate) fn is_secret_key(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    key.contains("token") || key.contains("secret") || key.contains("password") || key.ends_with("apikey") || key == "key"
+}
+
+fn mask_value(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if value.is_string() && is_secret_key(key) {
+                    *value = serde_json::Value::String(MASK.to_string());
+                } else {
+                    mask_value(value);
+                }
+            }
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(mask_value),
+        _ => {}
+    }
+}
+
+/// Whether `path` is an agent's auth-profiles.json.
+pub(crate) fn is_auth_profiles(path: &str) -> bool {
+    path.rsplit('/').next() == Some("auth-profiles.json")
+}
+
+// The auth-profiles.json fields that are not credentials, at the top level and in each profile
+const AUTH_FILE_KEYS: &[&str] = &["version", "profiles", "lastGood"];
+pub(crate) const AUTH_PROFILE_KEYS: &[&str] = &["type", "provider"];
+
+/// Replace every credential in an auth-profiles.json `value` with `replacement`,
+/// returning the dotted paths of those that were set. Profiles name their credentials
+/// differently by type (`token` for API keys, `access` and `refresh` for OAuth), so
+/// every field but the type and provider counts as one.
+pub(crate) fn blank_auth_credentials(value: &mut serde_json::Value, replacement: &str) -> Vec<String> {
+    let mut blanked = Vec::new();
+    let Some(file) = value.as_object_mut() else { return blanked };
+    file.retain(|key, _| AUTH_FILE_KEYS.contains(&key.as_str()));
+    let Some(profiles) = file.get_mut("profiles").and_then(|profiles| profiles.as_object_mut()) else { return blanked };
+    for (name, profile) in profiles.iter_mut() {
+        let Some(profile) = profile.as_object_mut() else {
+            *profile = serde_json::Value::String(replacement.to_string());
+            blanked.push(format!("profiles.{}", name));
+            continue;
+        };
+        for (key, field) in profile.iter_mut().filter(|(key, _)| !AUTH_PROFILE_KEYS.contains(&key.as_str())) {
+            if field.as_str() != Some("") {
+                blanked.push(format!("profiles.{}.{}", name, key));
+            }
+            *field = serde_json::Value::String(replacement.to_string());
+        }
+    }
+    blanked
+}
+
+/// `text` of the file at `path` with its secrets masked: every credential in an
+/// auth-profiles.json, and the string values of token, password and key fields in
+/// other JSON. Text that is not JSON is returned as is.
+pub(crate) fn mask_json(path: &str, text: &str) -> String {
+    let Ok(mut value) = serde_json::from_str::<serde_json::Value>(text) else { return text.to_string() };
+    if is_auth_profiles(path) {
+        blank_auth_credentials(&mut value, MASK);
+    } else {
+        mask_value(&mut value);
+    }
+    serde_json::to_string_pretty(&value).unwrap_or_else(|_| text.to_string())
+}
+
+fn mask_literals(text: &str, secrets: &[&str]) -> String {
+    // Very short values would mask unrelated text and give nothing away anyway
+    secrets.iter().filter(|secret| secret.len() >= 4).fold(text.to_string(), |text, secret| text.replace(secret, MASK))
+}
+
+/// Line operations turning `old` into `new`: ' ' keeps a line, '-' removes one, '+' adds one.
+fn diff_ops<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<(char, &'a str)> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..].iter().rev().zip(new[prefix..].iter().rev()).take_while(|(a, b)| a == b).count();
+    let (a, b) = (&old[prefix..old.len() - suffix], &new[prefix..new.len() - suffix]);
+
+    // lcs[i * width + j]: length of the longest common subsequence of a[i..] and b[j..]
+    let width = b.len() + 1;
+    let mut lcs = vec![0u32; (a.len() + 1) * width];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i * width + j] = if a[i] == b[j] {
+                lcs[(i + 1) * width + j + 1] + 1
+            } else {
+                lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut ops: Vec<(char, &str)> = old[..prefix].iter().map(|line| (' ', *line)).collect();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            ops.push((' ', a[i]));
+            i += 1;
+            j += 1;
+        } else if i < a.len() && (j == b.len() || lcs[(i + 1) * width + j] >= lcs[i * width + j + 1]) {
+            ops.push(('-', a[i]));
+            i += 1;
+        } else {
+            ops.push(('+', b[j]));
+            j += 1;
+        }
+    }
+    ops.extend(old[old.len() - suffix..].iter().map(|line| (' ', *line)));
+    ops
+}
+
+fn hunk_range(start: usize, len: usize) -> String {
+    if len == 0 {
+        format!("{},0", start)
+    } else {
+        format!("{},{}", start + 1, len)
+    }
+}
+
+/// A unified diff of `old` and `new` for `path`, empty when they are the same.
+pub(crate) fn unified_diff(path: &str, old: &str, new: &str) -> String {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let ops = diff_ops(&old_lines, &new_lines);
+    let changes: Vec<usize> = ops.iter().enumerate().filter(|(_, (op, _))| *op != ' ').map(|(i, _)| i).collect();
+    if changes.is_empty() {
+        return String::new();
+    }
+
+    // Old and new line counts before each op
+    let mut positions = Vec::with_capacity(ops.len());
+    let (mut old_at, mut new_at) = (0, 0);
+    for (op, _) in &ops {
+        positions.push((old_at, new_at));
+        match op {
+            ' ' => (old_at, new_at) = (old_at + 1, new_at + 1),
+            '-' => old_at += 1,
+            _ => new_at += 1,
+        }
+    }
+
+    let path = path.trim_start_matches('/');
+    let from = if old.is_empty() { "/dev/null".to_string() } else { format!("a/{}", path) };
+    let to = if new.is_empty() { "/dev/null".to_string() } else { format!("b/{}", path) };
+    let mut out = format!("--- {}\n+++ {}\n", from, to);
+    let mut next = 0;
+    while next < changes.len() {
+        let start = changes[next].saturating_sub(CONTEXT);
+        let mut end = changes[next];
+        // Changes close enough to share context go in one hunk
+        while next + 1 < changes.len() && changes[next + 1] - end <= 2 * CONTEXT {
+            next += 1;
+            end = changes[next];
+        }
+        let hunk = &ops[start..(end + CONTEXT + 1).min(ops.len())];
+        let (old_start, new_start) = positions[start];
+        let old_len = hunk.iter().filter(|(op, _)| *op != '+').count();
+        let new_len = hunk.iter().filter(|(op, _)| *op != '-').count();
+        out.push_str(&format!("@@ -{} +{} @@\n", hunk_range(old_start, old_len), hunk_range(new_start, new_len)));
+        for (op, line) in hunk {
+            out.push(*op);
+            out.push_str(line);
+            out.push('\n');
+        }
+        next += 1;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::configure_local;
+    use crate::test_support::{wizard_config, MemoryTarget};
+
+    #[test]
+    fn test_unified_diff_hunks() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\n";
+        let new = "a\nB\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\nm\n";
+        assert_eq!(
+            unified_diff("/home/sam/x.md", old, new),
+            "--- a/home/sam/x.md\n+++ b/home/sam/x.md\n\
+             @@ -1,5 +1,5 @@\n a\n-b\n+B\n c\n d\n e\n\
+             @@ -10,3 +10,4 @@\n j\n k\n l\n+m\n"
+        );
+        assert_eq!(unified_diff("x", old, old), "");
+        assert_eq!(unified_diff("/x", "", "one\n"), "--- /dev/null\n+++ b/x\n@@ -0,0 +1,1 @@\n+one\n");
+    }
+
+    #[test]
+    fn test_plan_configuration_diffs_files_and_masks_secrets() {
+        let mut machine = MemoryTarget::default();
+        machine.files.insert(
+            "/home/sam/.openclaw/openclaw.json".to_string(),
+            serde_json::json!({ "gateway": { "auth": { "token": "old-gateway-token" } }, "hooks": {} }).to_string(),
+        );
+        machine.files.insert("/home/sam/.openclaw/workspace/USER.md".to_string(), "# USER.md - About Your Human\n- **Name:** Sam\n---".to_string());
+        machine.files.insert(
+            "/home/sam/.openclaw/agents/main/agent/auth-profiles.json".to_string(),
+            serde_json::json!({ "profiles": { "openai:default": { "type": "oauth", "provider": "openai", "access": "old-oauth-access" } } }).to_string(),
+        );
+
+        let mut config = wizard_config();
+        config.merge_config = Some(true);
+        config.service_keys = Some([("brave".to_string(), "brave-secret-key".to_string())].into());
+        let plan = PlanTarget::new(&machine);
+        let merge = configure_local(&plan, &config, "/home/sam").unwrap();
+        let plan = plan.finish(merge, &["sk-secret", "123:abc", "brave-secret-key"]).unwrap();
+
+        let file = |name: &str| plan.files.iter().find(|f| f.path == format!("/home/sam/.openclaw/{}", name)).unwrap();
+        assert_eq!(file("openclaw.json").status, "modified");
+        assert_eq!(file("workspace/USER.md").status, "unchanged");
+        assert_eq!(file("workspace/USER.md").diff, "");
+        assert_eq!(file("workspace/SOUL.md").status, "added");
+        assert!(file("agents/research/agent/auth-profiles.json").diff.starts_with("--- /dev/null\n+++ b/home/sam/.openclaw/agents/"));
+
+        // The merge keeps hand-added keys and the gateway token, and says so
+        let merge = plan.merge.as_ref().unwrap();
+        assert!(merge.untouched.contains(&"hooks".to_string()));
+        assert!(!merge.changed.contains(&"gateway.auth.token".to_string()));
+
+        let everything = serde_json::to_string(&plan).unwrap();
+        for secret in ["sk-secret", "123:abc", "brave-secret-key", "old-gateway-token", "old-oauth-access"] {
+            assert!(!everything.contains(secret), "{} is masked", secret);
+        }
+        assert!(file("openclaw.json").diff.contains(r#""botToken": "********""#));
+
+        assert_eq!(
+            plan.commands[..4],
+            [
+                "openclaw gateway stop",
+                "openclaw gateway install --force",
+                "mkdir -p /home/sam/.openclaw/workspace",
+                "mkdir -p /home/sam/.openclaw/agents/main/agent",
+            ]
+        );
+    }
+}
@@ -0,0 +1,821 @@
+// Typed model of the openclaw.json that Clawnetes writes. Local and remote setup
+// both build it from the wizard's `AgentConfig` with `OpenClawConfig::build`, so
+// the same answers produce the same file on every target, and `get_current_config`
+// reads an existing file back through `OpenClawConfig::parse`.
+//
+// Field order follows the file as written; keys Clawnetes does not manage are
+// ignored when parsing. In merge mode the built config is applied onto the
+// existing document instead, so hand-added keys survive a reconfigure.
+
+use crate::{AgentConfig, AgentToolsConfig, SubagentConfig, DEFAULT_GATEWAY_PORT};
+use std::collections::BTreeMap;
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub(crate) struct OpenClawConfig {
+    pub(crate) messages: Messages,
+    pub(crate) agents: Agents,
+    pub(crate) gateway: Gateway,
+    pub(crate) auth: Auth,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) commands: Option<Commands>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) plugins: Option<Plugins>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) channels: Option<Channels>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) tools: Option<ToolsPolicy>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) cron: Option<Enabled>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub(crate) struct Messages {
+    pub(crate) ack_reaction_scope: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub(crate) struct Agents {
+    pub(crate) defaults: AgentDefaults,
+    pub(crate) list: Vec<AgentEntry>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub(crate) struct AgentDefaults {
+    pub(crate) max_concurrent: u32,
+    pub(crate) subagents: SubagentDefaults,
+    pub(crate) compaction: Compaction,
+    pub(crate) workspace: String,
+    pub(crate) model: ModelRef,
+    // Per-model settings keyed by model id; we only ever write empty ones
+    pub(crate) models: BTreeMap<String, serde_json::Map<String, serde_json::Value>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) heartbeat: Option<Heartbeat>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) sandbox: Option<Sandbox>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub(crate) struct SubagentDefaults {
+    pub(crate) max_concurrent: u32,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub(crate) struct Compaction {
+    pub(crate) mode: String,
+    #[serde(skip_serializing_if = "Option::is_none", deserialize_with = "memory_flush")]
+    pub(crate) memory_flush: Option<Enabled>,
+}
+
+/// `{ "enabled": bool }`, used by memoryFlush, cron and plugin entries.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub(crate) struct Enabled {
+    pub(crate) enabled: bool,
+}
+
+/// A model and its fallbacks. Agent entries may also give the model as a bare string.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase", from = "ModelSpec")]
+pub(crate) struct ModelRef {
+    pub(crate) primary: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) fallbacks: Option<Vec<String>>,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum ModelSpec {
+    Name(String),
+    Detailed {
+        #[serde(default)]
+        primary: String,
+        #[serde(default)]
+        fallbacks: Option<Vec<String>>,
+    },
+}
+
+impl From<ModelSpec> for ModelRef {
+    fn from(spec: ModelSpec) -> ModelRef {
+        match spec {
+            ModelSpec::Name(primary) => ModelRef { primary, fallbacks: None },
+            ModelSpec::Detailed { primary, fallbacks } => ModelRef { primary, fallbacks },
+        }
+    }
+}
+
+/// Older configs wrote memoryFlush as a bare boolean.
+fn memory_flush<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Option<Enabled>, D::Error> {
+    #[derive(serde::Deserialize)]
+    #[serde(untagged)]
+    enum Flag {
+        Bare(bool),
+        Object(Enabled),
+    }
+    Ok(match <Option<Flag> as serde::Deserialize>::deserialize(deserializer)? {
+        Some(Flag::Bare(enabled)) => Some(Enabled { enabled }),
+        Some(Flag::Object(flag)) => Some(flag),
+        None => None,
+    })
+}
+
+/// One of `{ "enabled": false }`, `{ "mode": "idle", "timeout": ms }` or `{ "every": "1h" }`.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub(crate) struct Heartbeat {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) enabled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) mode: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) timeout: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) every: Option<String>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub(crate) struct Sandbox {
+    pub(crate) mode: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub(crate) struct AgentEntry {
+    pub(crate) id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) default: Option<bool>,
+    pub(crate) name: String,
+    pub(crate) workspace: String,
+    pub(crate) agent_dir: String,
+    pub(crate) model: ModelRef,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) subagents: Option<SubagentConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) tools: Option<AgentToolsConfig>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub(crate) struct Gateway {
+    pub(crate) mode: String,
+    pub(crate) port: u16,
+    pub(crate) bind: String,
+    pub(crate) auth: GatewayAuth,
+    pub(crate) tailscale: Tailscale,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub(crate) struct GatewayAuth {
+    pub(crate) mode: String,
+    pub(crate) token: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub(crate) struct Tailscale {
+    pub(crate) mode: String,
+    pub(crate) reset_on_exit: bool,
+}
+
+// What OpenClaw assumes when the gateway section leaves a setting out
+impl Default for Gateway {
+    fn default() -> Gateway {
+        Gateway {
+            mode: String::new(),
+            port: DEFAULT_GATEWAY_PORT,
+            bind: "loopback".to_string(),
+            auth: GatewayAuth::default(),
+            tailscale: Tailscale::default(),
+        }
+    }
+}
+
+impl Default for GatewayAuth {
+    fn default() -> GatewayAuth {
+        GatewayAuth { mode: "token".to_string(), token: String::new() }
+    }
+}
+
+impl Default for Tailscale {
+    fn default() -> Tailscale {
+        Tailscale { mode: "off".to_string(), reset_on_exit: false }
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub(crate) struct Auth {
+    pub(crate) profiles: BTreeMap<String, AuthProfile>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub(crate) struct AuthProfile {
+    pub(crate) provider: String,
+    pub(crate) mode: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) base_url: Option<String>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub(crate) struct Commands {
+    pub(crate) native: String,
+    pub(crate) native_skills: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub(crate) struct Plugins {
+    pub(crate) entries: BTreeMap<String, Enabled>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub(crate) struct Channels {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) telegram: Option<TelegramChannel>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub(crate) struct TelegramChannel {
+    pub(crate) accounts: BTreeMap<String, TelegramAccount>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub(crate) struct TelegramAccount {
+    pub(crate) bot_token: String,
+    pub(crate) name: String,
+    pub(crate) dm_policy: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub(crate) struct ToolsPolicy {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) allow: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) deny: Option<Vec<String>>,
+}
+
+// Settings the wizard owns outright: replaced as a whole rather than merged key by
+// key, and removed when the wizard no longer sets them (fallbacks dropped, tools
+// switched from an allowlist to a denylist or to all tools, memory turned off).
+const OWNED_KEYS: &[&str] = &[
+    "agents.defaults.model",
+    "agents.defaults.heartbeat",
+    "agents.defaults.sandbox",
+    "agents.defaults.compaction.memoryFlush",
+    "tools.allow",
+    "tools.deny",
+];
+// The same for each entry of agents.list, relative to the entry
+const OWNED_AGENT_KEYS: &[&str] = &["model", "subagents"];
+// Sections the wizard writes only while a setting is on: merged key by key while
+// they are written, removed once the setting is turned off (Telegram disabled, cron
+// jobs cleared).
+const OPTIONAL_SECTIONS: &[&str] = &["channels.telegram.accounts.main", "plugins.entries.telegram", "cron"];
+
+/// What a merge did to the existing openclaw.json, as dotted key paths. Agents
+/// in `agents.list` are named by id, e.g. `agents.list.research.model`.
+#[derive(serde::Serialize, Clone, Debug, Default, PartialEq)]
+pub(crate) struct MergeReport {
+    pub(crate) changed: Vec<String>,
+    pub(crate) added: Vec<String>,
+    pub(crate) removed: Vec<String>,
+    // Keys Clawnetes does not manage, kept as they were
+    pub(crate) untouched: Vec<String>,
+}
+
+impl MergeReport {
+    /// One line for the install log.
+    pub(crate) fn summary(&self) -> String {
+        let parts: Vec<String> = [
+            ("changed", &self.changed),
+            ("added", &self.added),
+            ("removed", &self.removed),
+            ("left untouched", &self.untouched),
+        ]
+        .iter()
+        .filter(|(_, keys)| !keys.is_empty())
+        .map(|(what, keys)| format!("{} {}", what, keys.join(", ")))
+        .collect();
+        if parts.is_empty() {
+            "openclaw.json merged: no changes".to_string()
+        } else {
+            format!("openclaw.json merged: {}", parts.join("; "))
+        }
+    }
+}
+
+fn is_owned(path: &str) -> bool {
+    match path.strip_prefix("agents.list.") {
+        Some(entry) => entry.split_once('.').is_some_and(|(_, key)| OWNED_AGENT_KEYS.contains(&key)),
+        None => OWNED_KEYS.contains(&path),
+    }
+}
+
+/// Whether `path` is removed when the wizard no longer writes it.
+fn is_removable(path: &str) -> bool {
+    is_owned(path) || OPTIONAL_SECTIONS.contains(&path)
+}
+
+/// Whether the wizard manages anything inside `path`, e.g. `tools` for `tools.allow`.
+fn has_managed_keys_below(path: &str) -> bool {
+    let prefix = format!("{}.", path);
+    OWNED_KEYS.iter().chain(OPTIONAL_SECTIONS).any(|key| key.starts_with(&prefix))
+}
+
+fn child_path(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+fn merge_object(
+    current: &mut serde_json::Map<String, serde_json::Value>,
+    managed: serde_json::Map<String, serde_json::Value>,
+    path: &str,
+    report: &mut MergeReport,
+) {
+    let kept: Vec<String> = current.keys().filter(|key| !managed.contains_key(*key)).cloned().collect();
+    for key in kept {
+        let key_path = child_path(path, &key);
+        if is_removable(&key_path) {
+            current.remove(&key);
+            report.removed.push(key_path);
+        } else if let Some(serde_json::Value::Object(section)) = current.get_mut(&key).filter(|_| has_managed_keys_below(&key_path)) {
+            // The wizard no longer writes this section (tools set to "all", Telegram off):
+            // drop what it managed there and keep the rest, or the section if nothing is left
+            merge_object(section, serde_json::Map::new(), &key_path, report);
+            if section.is_empty() {
+                current.remove(&key);
+            }
+        } else {
+            report.untouched.push(key_path);
+        }
+    }
+    for (key, value) in managed {
+        let key_path = child_path(path, &key);
+        match current.get_mut(&key) {
+            Some(existing) => merge_value(existing, value, &key_path, report),
+            None => {
+                report.added.push(key_path);
+                current.insert(key, value);
+            }
+        }
+    }
+}
+
+fn merge_value(current: &mut serde_json::Value, managed: serde_json::Value, path: &str, report: &mut MergeReport) {
+    match (current, managed) {
+        (serde_json::Value::Array(existing), serde_json::Value::Array(agents)) if path == "agents.list" => {
+            merge_agents(existing, agents, report)
+        }
+        (serde_json::Value::Object(existing), serde_json::Value::Object(managed)) if !is_owned(path) => {
+            merge_object(existing, managed, path, report)
+        }
+        (current, managed) => {
+            if *current != managed {
+                report.changed.push(path.to_string());
+                *current = managed;
+            }
+        }
+    }
+}
+
+/// Agents are matched by id. The wizard's list decides which agents exist and in
+/// what order; keys it does not manage on an existing agent are kept.
+fn merge_agents(current: &mut Vec<serde_json::Value>, managed: Vec<serde_json::Value>, report: &mut MergeReport) {
+    let id_of = |agent: &serde_json::Value| agent.get("id").and_then(|id| id.as_str()).unwrap_or_default().to_string();
+    let mut previous: Vec<serde_json::Value> = std::mem::take(current);
+    for agent in managed {
+        let id = id_of(&agent);
+        let agent_path = format!("agents.list.{}", id);
+        match previous.iter().position(|existing| id_of(existing) == id) {
+            Some(index) => {
+                let mut existing = previous.remove(index);
+                merge_value(&mut existing, agent, &agent_path, report);
+                current.push(existing);
+            }
+            None => {
+                report.added.push(agent_path);
+                current.push(agent);
+            }
+        }
+    }
+    for agent in previous {
+        report.removed.push(format!("agents.list.{}", id_of(&agent)));
+    }
+}
+
+/// The auth profile mode OpenClaw expects for the wizard's auth method.
+pub(crate) fn auth_mode(auth_method: Option<&str>) -> String {
+    match auth_method.unwrap_or("token") {
+        "setup-token" => "token".to_string(),
+        "antigravity" | "gemini_cli" | "codex" => "oauth".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// The wizard's sandbox choice in OpenClaw's terms, and back.
+fn sandbox_to_openclaw(mode: &str) -> &str {
+    match mode {
+        "full" => "all",
+        "partial" => "non-main",
+        "none" => "off",
+        other => other,
+    }
+}
+
+fn sandbox_from_openclaw(mode: &str) -> &str {
+    match mode {
+        "all" => "full",
+        "non-main" => "partial",
+        "off" => "none",
+        other => other,
+    }
+}
+
+fn non_empty(list: &Option<Vec<String>>) -> Option<Vec<String>> {
+    list.clone().filter(|l| !l.is_empty())
+}
+
+impl OpenClawConfig {
+    /// The config for the wizard's answers. `home` is the home directory on the target
+    /// (the WSL home on Windows); `gateway_token` is kept from an earlier install so
+    /// paired devices stay valid.
+    pub(crate) fn build(config: &AgentConfig, home: &str, gateway_token: &str) -> OpenClawConfig {
+        let openclaw_root = format!("{}/.openclaw", home);
+        let workspace = format!("{}/workspace", openclaw_root);
+        let primary = ModelRef { primary: config.model.clone(), fallbacks: non_empty(&config.fallback_models) };
+
+        let mut list: Vec<AgentEntry> = config
+            .agents
+            .iter()
+            .flatten()
+            .map(|agent| AgentEntry {
+                id: agent.id.clone(),
+                default: None,
+                name: agent.name.clone(),
+                workspace: format!("{}/agents/{}/workspace", openclaw_root, agent.id),
+                agent_dir: format!("{}/agents/{}/agent", openclaw_root, agent.id),
+                model: ModelRef { primary: agent.model.clone(), fallbacks: non_empty(&agent.fallback_models) },
+                subagents: agent.subagents.clone(),
+                tools: None,
+            })
+            .collect();
+        if !list.iter().any(|agent| agent.id == "main") {
+            list.insert(
+                0,
+                AgentEntry {
+                    id: "main".to_string(),
+                    default: Some(true),
+                    name: config.agent_name.clone(),
+                    workspace: workspace.clone(),
+                    agent_dir: format!("{}/agents/main/agent", openclaw_root),
+                    model: primary.clone(),
+                    subagents: None,
+                    tools: None,
+                },
+            );
+        }
+
+        let heartbeat = config.heartbeat_mode.as_deref().map(|mode| match mode {
+            "never" => Heartbeat { enabled: Some(false), ..Default::default() },
+            "idle" => Heartbeat {
+                mode: Some("idle".to_string()),
+                timeout: Some(config.idle_timeout_ms.unwrap_or(3600000)),
+                ..Default::default()
+            },
+            interval => Heartbeat { every: Some(interval.to_string()), ..Default::default() },
+        });
+
+        let mut profiles = BTreeMap::new();
+        profiles.insert(
+            format!("{}:default", config.provider),
+            AuthProfile {
+                provider: config.provider.clone(),
+                mode: auth_mode(config.auth_method.as_deref()),
+                base_url: config.base_url.clone().filter(|url| !url.is_empty()),
+            },
+        );
+
+        // Telegram is configured inline; `openclaw config set` would trigger hot reloads mid-setup
+        let telegram_token = config.telegram_token.clone().filter(|token| !token.is_empty());
+        let plugins = telegram_token.as_ref().map(|_| Plugins {
+            entries: BTreeMap::from([("telegram".to_string(), Enabled { enabled: true })]),
+        });
+        let channels = telegram_token.map(|bot_token| {
+            let account = TelegramAccount {
+                bot_token,
+                name: "Primary Bot".to_string(),
+                // A reconfigure keeps the users already paired; a fresh install starts pairing
+                dm_policy: if config.preserve_state == Some(true) { "allowlist" } else { "pairing" }.to_string(),
+            };
+            Channels { telegram: Some(TelegramChannel { accounts: BTreeMap::from([("main".to_string(), account)]) }) }
+        });
+
+        let tools = match config.tools_mode.as_deref() {
+            Some("allowlist") => config.allowed_tools.clone().map(|allow| ToolsPolicy { allow: Some(allow), deny: None }),
+            Some("denylist") => config.denied_tools.clone().map(|deny| ToolsPolicy { allow: None, deny: Some(deny) }),
+            _ => None,
+        };
+
+        OpenClawConfig {
+            messages: Messages { ack_reaction_scope: "group-mentions".to_string() },
+            agents: Agents {
+                defaults: AgentDefaults {
+                    max_concurrent: 4,
+                    subagents: SubagentDefaults { max_concurrent: 8 },
+                    compaction: Compaction {
+                        mode: "safeguard".to_string(),
+                        memory_flush: config.memory_enabled.unwrap_or(false).then_some(Enabled { enabled: true }),
+                    },
+                    workspace,
+                    models: BTreeMap::from([(config.model.clone(), serde_json::Map::new())]),
+                    model: primary,
+                    heartbeat,
+                    sandbox: config.sandbox_mode.as_deref().map(|mode| Sandbox { mode: sandbox_to_openclaw(mode).to_string() }),
+                },
+                list,
+            },
+            gateway: Gateway {
+                mode: "local".to_string(),
+                port: config.gateway_port.unwrap_or(DEFAULT_GATEWAY_PORT),
+                bind: config.gateway_bind.clone().unwrap_or_else(|| "loopback".to_string()),
+                auth: GatewayAuth {
+                    mode: config.gateway_auth_mode.clone().unwrap_or_else(|| "token".to_string()),
+                    token: gateway_token.to_string(),
+                },
+                tailscale: Tailscale {
+                    mode: config.tailscale_mode.clone().unwrap_or_else(|| "off".to_string()),
+                    reset_on_exit: false,
+                },
+            },
+            auth: Auth { profiles },
+            commands: Some(Commands { native: "auto".to_string(), native_skills: "auto".to_string() }),
+            plugins,
+            channels,
+            tools,
+            cron: config.cron_jobs.as_ref().is_some_and(|jobs| !jobs.is_empty()).then_some(Enabled { enabled: true }),
+        }
+    }
+
+    /// Parse an existing openclaw.json. Missing sections take their defaults and keys
+    /// Clawnetes does not manage are skipped.
+    pub(crate) fn parse(text: &str) -> Result<OpenClawConfig, String> {
+        serde_json::from_str(text).map_err(|e| format!("Failed to parse openclaw.json: {}", e))
+    }
+
+    /// The file contents, as written to disk on every target.
+    pub(crate) fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string_pretty(self).map_err(|e| e.to_string())
+    }
+
+    /// Apply the settings Clawnetes manages onto `existing`, the current openclaw.json
+    /// (empty when there is none), keeping every other key. Returns the new contents
+    /// and what changed.
+    pub(crate) fn merge_into(&self, existing: &str) -> Result<(String, MergeReport), String> {
+        let mut document = if existing.trim().is_empty() {
+            serde_json::Value::Object(serde_json::Map::new())
+        } else {
+            serde_json::from_str(existing)
+                .map_err(|e| format!("Cannot merge into openclaw.json, it is not valid JSON: {}", e))?
+        };
+        if !document.is_object() {
+            return Err("Cannot merge into openclaw.json, it is not a JSON object".to_string());
+        }
+        let managed = serde_json::to_value(self).map_err(|e| e.to_string())?;
+        let mut report = MergeReport::default();
+        merge_value(&mut document, managed, "", &mut report);
+        let text = serde_json::to_string_pretty(&document).map_err(|e| e.to_string())?;
+        Ok((text, report))
+    }
+
+    /// The Telegram account Clawnetes manages, if one is configured.
+    pub(crate) fn telegram_account(&self) -> Option<&TelegramAccount> {
+        self.channels.as_ref()?.telegram.as_ref()?.accounts.get("main")
+    }
+
+    /// The wizard's sandbox choice; OpenClaw sandboxes everything when unset.
+    pub(crate) fn sandbox_mode(&self) -> String {
+        let mode = self.agents.defaults.sandbox.as_ref().map(|s| s.mode.as_str()).unwrap_or("all");
+        sandbox_from_openclaw(mode).to_string()
+    }
+
+    /// The wizard's heartbeat choice: "never", "idle" or an interval such as "1h".
+    pub(crate) fn heartbeat_mode(&self) -> String {
+        let Some(heartbeat) = &self.agents.defaults.heartbeat else { return "1h".to_string() };
+        if heartbeat.enabled == Some(false) {
+            "never".to_string()
+        } else {
+            heartbeat.mode.clone().or_else(|| heartbeat.every.clone()).unwrap_or_else(|| "1h".to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::wizard_config;
+
+    #[test]
+    fn test_openclaw_config_built_from_wizard_answers() {
+        let built = OpenClawConfig::build(&wizard_config(), "/home/sam", "gw-token");
+        let json: serde_json::Value = serde_json::from_str(&built.to_json().unwrap()).unwrap();
+
+        let keys: Vec<&String> = json.as_object().unwrap().keys().collect();
+        assert_eq!(keys, ["messages", "agents", "gateway", "auth", "commands", "plugins", "channels", "tools", "cron"]);
+
+        let defaults = &json["agents"]["defaults"];
+        assert_eq!(defaults["workspace"], "/home/sam/.openclaw/workspace");
+        assert_eq!(defaults["model"], serde_json::json!({ "primary": "openai/gpt-5", "fallbacks": ["anthropic/claude-sonnet-4-5"] }));
+        assert_eq!(defaults["models"], serde_json::json!({ "openai/gpt-5": {} }));
+        assert_eq!(defaults["heartbeat"], serde_json::json!({ "mode": "idle", "timeout": 3600000 }));
+        assert_eq!(defaults["sandbox"]["mode"], "non-main");
+        assert_eq!(defaults["compaction"]["memoryFlush"]["enabled"], true);
+
+        // The main agent is added in front of the wizard's agents and marked default
+        let list = json["agents"]["list"].as_array().unwrap();
+        assert_eq!(list[0]["id"], "main");
+        assert_eq!(list[0]["default"], true);
+        assert_eq!(list[0]["agentDir"], "/home/sam/.openclaw/agents/main/agent");
+        assert_eq!(list[1]["model"], serde_json::json!({ "primary": "anthropic/claude-opus-4-6" }));
+        assert_eq!(list[1]["subagents"]["allowAgents"], serde_json::json!(["main"]));
+
+        assert_eq!(json["gateway"]["port"], 28789);
+        assert_eq!(json["gateway"]["auth"], serde_json::json!({ "mode": "token", "token": "gw-token" }));
+        assert_eq!(
+            json["auth"]["profiles"]["openai:default"],
+            serde_json::json!({ "provider": "openai", "mode": "oauth", "baseUrl": "https://llm.internal/v1" })
+        );
+        assert_eq!(json["channels"]["telegram"]["accounts"]["main"]["dmPolicy"], "pairing");
+        assert_eq!(json["tools"], serde_json::json!({ "deny": ["exec"] }));
+
+        // API keys live in auth-profiles.json, never in openclaw.json
+        assert!(!built.to_json().unwrap().contains("sk-secret"));
+    }
+
+    #[test]
+    fn test_openclaw_config_round_trips() {
+        let built = OpenClawConfig::build(&wizard_config(), "/home/sam", "gw-token");
+        let text = built.to_json().unwrap();
+        let parsed = OpenClawConfig::parse(&text).unwrap();
+        assert_eq!(parsed, built);
+        assert_eq!(parsed.to_json().unwrap(), text);
+
+        assert_eq!(parsed.sandbox_mode(), "partial");
+        assert_eq!(parsed.heartbeat_mode(), "idle");
+        assert_eq!(parsed.telegram_account().map(|a| a.bot_token.as_str()), Some("123:abc"));
+
+        // A minimal config parses too: no Telegram, no tools, and the default for everything else
+        let minimal = OpenClawConfig::build(
+            &serde_json::from_value(serde_json::json!({
+                "provider": "anthropic", "api_key": "", "model": "anthropic/claude-opus-4-6",
+                "user_name": "", "agent_name": "Claw", "heartbeat_mode": "never"
+            }))
+            .unwrap(),
+            "/root",
+            "t",
+        );
+        assert_eq!(OpenClawConfig::parse(&minimal.to_json().unwrap()).unwrap(), minimal);
+        assert_eq!(minimal.heartbeat_mode(), "never");
+        assert!(minimal.telegram_account().is_none() && minimal.tools.is_none() && minimal.cron.is_none());
+    }
+
+    #[test]
+    fn test_openclaw_config_parses_older_and_foreign_files() {
+        // Bare-string agent models, a bare-bool memoryFlush and keys we do not manage
+        let parsed = OpenClawConfig::parse(
+            r#"{
+                "agents": {
+                    "defaults": { "compaction": { "memoryFlush": true }, "model": "openai/gpt-5" },
+                    "list": [{ "id": "helper", "model": "openai/gpt-4o", "extra": 1 }]
+                },
+                "gateway": { "auth": { "token": "abc" } },
+                "hooks": { "internal": { "enabled": true } }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(parsed.agents.defaults.compaction.memory_flush.as_ref().map(|f| f.enabled), Some(true));
+        assert_eq!(parsed.agents.defaults.model.primary, "openai/gpt-5");
+        assert_eq!(parsed.agents.list[0].model.primary, "openai/gpt-4o");
+        assert_eq!(parsed.gateway.port, DEFAULT_GATEWAY_PORT);
+        assert_eq!(parsed.gateway.bind, "loopback");
+        assert_eq!(parsed.gateway.auth.token, "abc");
+        assert_eq!(parsed.sandbox_mode(), "full");
+        assert_eq!(parsed.heartbeat_mode(), "1h");
+
+        // A file without a gateway section still gets OpenClaw's defaults
+        assert_eq!(OpenClawConfig::parse("{}").unwrap().gateway.port, DEFAULT_GATEWAY_PORT);
+        assert!(OpenClawConfig::parse("not json").is_err());
+    }
+
+    #[test]
+    fn test_openclaw_config_merge_keeps_hand_added_keys() {
+        let existing = serde_json::json!({
+            "agents": {
+                "defaults": {
+                    "model": { "primary": "openai/gpt-4o", "fallbacks": ["openai/gpt-4o-mini"] },
+                    "workspace": "/home/sam/.openclaw/workspace",
+                    "contextTokens": 64000
+                },
+                "list": [
+                    { "id": "main", "name": "Claw", "identity": { "emoji": "🦞" } },
+                    { "id": "old", "name": "Retired" }
+                ]
+            },
+            "gateway": { "port": 18789, "auth": { "mode": "token", "token": "gw-token" }, "controlUi": { "enabled": true } },
+            "tools": { "allow": ["read"], "profile": "coding" },
+            "hooks": { "internal": { "enabled": true } }
+        });
+        let mut config = wizard_config();
+        config.fallback_models = None;
+        config.agents = None;
+        let built = OpenClawConfig::build(&config, "/home/sam", "gw-token");
+        let (text, report) = built.merge_into(&existing.to_string()).unwrap();
+        let merged: serde_json::Value = serde_json::from_str(&text).unwrap();
+
+        // Keys we do not manage survive, at every depth
+        assert_eq!(merged["hooks"], existing["hooks"]);
+        assert_eq!(merged["gateway"]["controlUi"], existing["gateway"]["controlUi"]);
+        assert_eq!(merged["agents"]["defaults"]["contextTokens"], 64000);
+        assert_eq!(merged["agents"]["list"][0]["identity"]["emoji"], "🦞");
+        assert_eq!(merged["tools"], serde_json::json!({ "profile": "coding", "deny": ["exec"] }));
+
+        // Owned settings are replaced whole; dropped fallbacks and agents go away
+        assert_eq!(merged["agents"]["defaults"]["model"], serde_json::json!({ "primary": "openai/gpt-5" }));
+        assert_eq!(merged["agents"]["list"].as_array().unwrap().len(), 1);
+        assert_eq!(merged["gateway"]["port"], 28789);
+
+        for key in ["agents.defaults.model", "gateway.port"] {
+            assert!(report.changed.contains(&key.to_string()), "{} changed", key);
+        }
+        for key in ["tools.deny", "cron", "channels", "agents.list.main.default"] {
+            assert!(report.added.contains(&key.to_string()), "{} added", key);
+        }
+        assert_eq!(report.removed, ["agents.list.old", "tools.allow"]);
+        for key in ["hooks", "gateway.controlUi", "tools.profile", "agents.defaults.contextTokens", "agents.list.main.identity"] {
+            assert!(report.untouched.contains(&key.to_string()), "{} untouched", key);
+        }
+        assert!(!report.changed.contains(&"gateway.auth.token".to_string()));
+
+        // Merging the same answers again changes nothing
+        let (again, report) = built.merge_into(&text).unwrap();
+        assert_eq!(again, text);
+        assert!(report.changed.is_empty() && report.added.is_empty() && report.removed.is_empty());
+
+        // An empty target gets the plain config; a broken file is left alone
+        assert_eq!(built.merge_into("").unwrap().0, built.to_json().unwrap());
+        assert!(built.merge_into("{ broken").is_err());
+    }
+
+    #[test]
+    fn test_openclaw_config_merge_removes_settings_turned_off() {
+        let mut config = wizard_config();
+        config.tools_mode = Some("allowlist".to_string());
+        config.allowed_tools = Some(vec!["read".to_string()]);
+        let before = OpenClawConfig::build(&config, "/home/sam", "gw-token").to_json().unwrap();
+        let mut existing: serde_json::Value = serde_json::from_str(&before).unwrap();
+        existing["tools"]["profile"] = "coding".into();
+        existing["channels"]["discord"] = serde_json::json!({ "enabled": true });
+        existing["channels"]["telegram"]["accounts"]["main"]["allowFrom"] = serde_json::json!(["42"]);
+
+        // Allowlist switched to all tools, Telegram turned off, cron jobs cleared
+        config.tools_mode = Some("all".to_string());
+        config.telegram_token = None;
+        config.cron_jobs = None;
+        let built = OpenClawConfig::build(&config, "/home/sam", "gw-token");
+        let (text, report) = built.merge_into(&existing.to_string()).unwrap();
+        let merged: serde_json::Value = serde_json::from_str(&text).unwrap();
+
+        assert_eq!(merged["tools"], serde_json::json!({ "profile": "coding" }));
+        assert_eq!(merged["channels"], serde_json::json!({ "discord": { "enabled": true } }));
+        assert!(merged.get("plugins").is_none());
+        assert!(merged.get("cron").is_none());
+        for key in ["tools.allow", "channels.telegram.accounts.main", "plugins.entries.telegram", "cron"] {
+            assert!(report.removed.contains(&key.to_string()), "{} removed", key);
+        }
+        for key in ["tools.profile", "channels.discord"] {
+            assert!(report.untouched.contains(&key.to_string()), "{} untouched", key);
+        }
+
+        // Turned back on, the Telegram account is merged rather than replaced
+        let (text, _) = OpenClawConfig::build(&wizard_config(), "/home/sam", "gw-token").merge_into(&existing.to_string()).unwrap();
+        let merged: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(merged["channels"]["telegram"]["accounts"]["main"]["allowFrom"], serde_json::json!(["42"]));
+        assert_eq!(merged["plugins"]["entries"]["telegram"]["enabled"], true);
+    }
+}
@@ -0,0 +1,311 @@
+// Preflight report for a remote host, run before provisioning it.
+//
+// Each check gathers one fact over SSH and grades it: `Fail` means setup
+// cannot succeed as things stand, `Warn` means it will work but differently
+// than usual (or with a risk the user should know about). The grading is kept
+// in pure functions so the thresholds can be tested without a server.
+
+use crate::remote_host::{self, SudoMode};
+use crate::remote_service::{self, ServiceManager};
+use crate::{execute_ssh_timeout, get_env_prefix, RemoteInfo, PROBE_TIMEOUT};
+use ssh2::Session;
+
+const REQUIRED_NODE_MAJOR: u32 = 22;
+const NPM_REGISTRY: &str = "https://registry.npmjs.org/";
+// Free space in $HOME: Node.js, OpenClaw and its workspace need a few hundred MB
+const DISK_FAIL_MB: u64 = 1024;
+const DISK_WARN_MB: u64 = 3 * 1024;
+const RAM_FAIL_MB: u64 = 512;
+const RAM_WARN_MB: u64 = 2 * 1024;
+
+#[derive(serde::Serialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+#[derive(serde::Serialize, Debug)]
+pub(crate) struct PreflightCheck {
+    // Stable identifier, e.g. "disk"
+    pub(crate) id: &'static str,
+    pub(crate) label: &'static str,
+    pub(crate) status: CheckStatus,
+    // What we found, or what is wrong and what setup will do about it
+    pub(crate) detail: String,
+}
+
+#[derive(serde::Serialize)]
+pub(crate) struct PreflightReport {
+    // The worst status among the checks
+    pub(crate) status: CheckStatus,
+    pub(crate) checks: Vec<PreflightCheck>,
+}
+
+fn check(id: &'static str, label: &'static str, status: CheckStatus, detail: impl Into<String>) -> PreflightCheck {
+    PreflightCheck { id, label, status, detail: detail.into() }
+}
+
+/// Major version from `node -v` output ("v22.11.0").
+pub(crate) fn parse_node_major(version: &str) -> Option<u32> {
+    version.trim().trim_start_matches('v').split('.').next()?.parse().ok()
+}
+
+/// Available kilobytes from the last line of `df -Pk`.
+pub(crate) fn parse_df_available_kb(output: &str) -> Option<u64> {
+    output.lines().last()?.split_whitespace().nth(3)?.parse().ok()
+}
+
+/// Total RAM in MB from /proc/meminfo (Linux) or `sysctl -n hw.memsize` (macOS, bytes).
+pub(crate) fn parse_total_ram_mb(output: &str) -> Option<u64> {
+    let output = output.trim();
+    if let Some(line) = output.lines().find(|l| l.starts_with("MemTotal:")) {
+        let kb: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+        return Some(kb / 1024);
+    }
+    output.parse::<u64>().ok().map(|bytes| bytes / (1024 * 1024))
+}
+
+/// Whether `ss -ltn` or `netstat -an` output shows something listening on `port`.
+/// ss and Linux netstat write "addr:port", BSD netstat "addr.port".
+pub(crate) fn port_is_listening(output: &str, port: u16) -> bool {
+    let suffixes = [format!(":{}", port), format!(".{}", port)];
+    output.lines().any(|line| {
+        let listening = line.contains("LISTEN");
+        listening && line.split_whitespace().any(|field| suffixes.iter().any(|s| field.ends_with(s.as_str())))
+    })
+}
+
+pub(crate) fn grade_arch(arch: &str) -> (CheckStatus, String) {
+    match arch {
+        "x86_64" | "amd64" | "aarch64" | "arm64" => (CheckStatus::Pass, arch.to_string()),
+        // Node.js 22 still ships armv7 builds, but distro packages and nvm binaries are hit and miss
+        "armv7l" | "armv7" => (CheckStatus::Warn, format!("{}: 32-bit ARM has limited Node.js {} support", arch, REQUIRED_NODE_MAJOR)),
+        "" => (CheckStatus::Fail, "Could not determine the CPU architecture".to_string()),
+        other => (CheckStatus::Fail, format!("{}: Node.js {} has no builds for this architecture", other, REQUIRED_NODE_MAJOR)),
+    }
+}
+
+pub(crate) fn grade_disk(available_mb: u64) -> (CheckStatus, String) {
+    let detail = format!("{:.1} GB free in $HOME", available_mb as f64 / 1024.0);
+    if available_mb < DISK_FAIL_MB {
+        (CheckStatus::Fail, format!("{}, at least {} GB needed", detail, DISK_FAIL_MB / 1024))
+    } else if available_mb < DISK_WARN_MB {
+        (CheckStatus::Warn, format!("{}, {} GB or more recommended", detail, DISK_WARN_MB / 1024))
+    } else {
+        (CheckStatus::Pass, detail)
+    }
+}
+
+pub(crate) fn grade_ram(total_mb: u64) -> (CheckStatus, String) {
+    let detail = format!("{} MB total", total_mb);
+    if total_mb < RAM_FAIL_MB {
+        (CheckStatus::Fail, format!("{}, at least {} MB needed", detail, RAM_FAIL_MB))
+    } else if total_mb < RAM_WARN_MB {
+        (CheckStatus::Warn, format!("{}, {} GB or more recommended", detail, RAM_WARN_MB / 1024))
+    } else {
+        (CheckStatus::Pass, detail)
+    }
+}
+
+/// `held_by_gateway` is None when the port is free, otherwise whether the process on it
+/// is the OpenClaw gateway. Setup restarts its own gateway, but cannot free the port
+/// from another program.
+pub(crate) fn grade_port(port: u16, held_by_gateway: Option<bool>) -> (CheckStatus, String) {
+    match held_by_gateway {
+        None => (CheckStatus::Pass, format!("Port {} is free", port)),
+        Some(true) => (CheckStatus::Warn, format!("Port {} is in use by the running OpenClaw gateway; setup will restart it", port)),
+        Some(false) => (
+            CheckStatus::Fail,
+            format!("Port {} is already in use by another program; stop it or choose another gateway port", port),
+        ),
+    }
+}
+
+/// Setup installs Node.js only when it is missing, so an old version is a failure.
+pub(crate) fn grade_node(version: Option<&str>) -> (CheckStatus, String) {
+    match version.map(|v| (v.trim(), parse_node_major(v))) {
+        None => (CheckStatus::Warn, format!("Not installed; setup will install Node.js {}", REQUIRED_NODE_MAJOR)),
+        Some((v, Some(major))) if major >= REQUIRED_NODE_MAJOR => (CheckStatus::Pass, v.to_string()),
+        Some((v, _)) => (
+            CheckStatus::Fail,
+            format!("{} is installed, but OpenClaw needs Node.js {} or newer. Upgrade or remove it first.", v, REQUIRED_NODE_MAJOR),
+        ),
+    }
+}
+
+/// Run every check against the host behind `sess`. `gateway_port` is the port the
+/// gateway is going to listen on.
+pub(crate) fn run(sess: &Session, remote: &RemoteInfo, gateway_port: u16) -> PreflightReport {
+    let probe = |cmd: &str| execute_ssh_timeout(sess, cmd, PROBE_TIMEOUT).ok().map(|s| s.trim().to_string());
+    let os = probe("uname -s").unwrap_or_default();
+    let env = get_env_prefix(&os);
+    let mut checks = Vec::new();
+
+    // Operating system and distribution
+    let release = if os == "Linux" { remote_host::read_os_release(sess).ok() } else { None };
+    checks.push(match os.as_str() {
+        "Linux" => match &release {
+            Some(r) if r.family().is_some() => check("os", "Operating system", CheckStatus::Pass, r.display_name()),
+            Some(r) => check("os", "Operating system", CheckStatus::Warn, format!("{} is not a tested distribution", r.display_name())),
+            None => check("os", "Operating system", CheckStatus::Warn, "Linux, distribution unknown"),
+        },
+        "Darwin" => {
+            let version = probe("sw_vers -productVersion").unwrap_or_default();
+            check("os", "Operating system", CheckStatus::Pass, format!("macOS {}", version).trim().to_string())
+        }
+        "" => check("os", "Operating system", CheckStatus::Fail, "Could not determine the operating system"),
+        other => check("os", "Operating system", CheckStatus::Fail, format!("{} is not supported; use Linux or macOS", other)),
+    });
+
+    let (status, detail) = grade_arch(&probe("uname -m").unwrap_or_default());
+    checks.push(check("arch", "CPU architecture", status, detail));
+
+    checks.push(match probe("df -Pk \"$HOME\"").as_deref().and_then(parse_df_available_kb) {
+        Some(kb) => {
+            let (status, detail) = grade_disk(kb / 1024);
+            check("disk", "Free disk space", status, detail)
+        }
+        None => check("disk", "Free disk space", CheckStatus::Warn, "Could not read free space in $HOME"),
+    });
+
+    let ram_cmd = if os == "Darwin" { "sysctl -n hw.memsize" } else { "cat /proc/meminfo" };
+    checks.push(match probe(ram_cmd).as_deref().and_then(parse_total_ram_mb) {
+        Some(mb) => {
+            let (status, detail) = grade_ram(mb);
+            check("ram", "Memory", status, detail)
+        }
+        None => check("ram", "Memory", CheckStatus::Warn, "Could not read total memory"),
+    });
+
+    let node = probe(&format!("{}node -v", env));
+    let (status, detail) = grade_node(node.as_deref());
+    checks.push(check("node", "Node.js", status, detail));
+    let node_ok = status == CheckStatus::Pass;
+
+    let privilege = remote_host::detect_privilege(sess, remote.sudo_password.as_deref());
+    let has_root = privilege.as_ref().is_ok_and(|p| p.mode != SudoMode::Unavailable);
+
+    // Package manager: only needed when Node.js has to be installed as root
+    checks.push(match os.as_str() {
+        "Linux" => match remote_host::detect_package_manager(sess) {
+            Ok(pm) => check("package_manager", "Package manager", CheckStatus::Pass, pm.name()),
+            Err(_) if node_ok => check("package_manager", "Package manager", CheckStatus::Pass, "Not needed, Node.js is already installed"),
+            Err(_) if !has_root => check("package_manager", "Package manager", CheckStatus::Pass, "Not needed, Node.js will be installed with nvm"),
+            Err(e) => check(
+                "package_manager",
+                "Package manager",
+                CheckStatus::Fail,
+                format!("{} Install Node.js {} yourself and run setup again.", e, REQUIRED_NODE_MAJOR),
+            ),
+        },
+        "Darwin" => match probe(&format!("{}command -v brew", env)) {
+            Some(_) => check("package_manager", "Package manager", CheckStatus::Pass, "Homebrew"),
+            None if node_ok => check("package_manager", "Package manager", CheckStatus::Pass, "Not needed, Node.js is already installed"),
+            None => check("package_manager", "Package manager", CheckStatus::Warn, "Homebrew is missing; setup will install it"),
+        },
+        _ => check("package_manager", "Package manager", CheckStatus::Fail, "Unsupported operating system"),
+    });
+
+    checks.push(match privilege {
+        Ok(p) => match p.mode {
+            SudoMode::Root => check("sudo", "Root access", CheckStatus::Pass, "Logged in as root"),
+            SudoMode::Passwordless => check("sudo", "Root access", CheckStatus::Pass, "Passwordless sudo"),
+            SudoMode::Password => check("sudo", "Root access", CheckStatus::Pass, "sudo with password"),
+            SudoMode::Unavailable => check(
+                "sudo",
+                "Root access",
+                CheckStatus::Warn,
+                "No sudo access; Node.js and OpenClaw will be installed in the home directory",
+            ),
+        },
+        Err(e) => check("sudo", "Root access", CheckStatus::Fail, e),
+    });
+
+    let manager = remote_service::detect_service_manager(sess);
+    checks.push(match manager {
+        ServiceManager::Systemd => match remote_service::linger_enabled(sess) {
+            Some(false) => check(
+                "service_manager",
+                "Service manager",
+                CheckStatus::Warn,
+                "systemd user manager, but lingering is off: the gateway stops when you log out",
+            ),
+            _ => check("service_manager", "Service manager", CheckStatus::Pass, "systemd user manager"),
+        },
+        ServiceManager::Launchd => check("service_manager", "Service manager", CheckStatus::Pass, "launchd"),
+        ServiceManager::Supervised => check(
+            "service_manager",
+            "Service manager",
+            CheckStatus::Warn,
+            "No systemd user manager; the gateway will run under a background supervisor and not restart after a reboot",
+        ),
+    });
+
+    let listeners = probe("ss -ltn 2>/dev/null || netstat -an 2>/dev/null").unwrap_or_default();
+    let (status, detail) = if port_is_listening(&listeners, gateway_port) {
+        grade_port(gateway_port, Some(remote_service::gateway_running(sess, manager)))
+    } else {
+        grade_port(gateway_port, None)
+    };
+    checks.push(check("port", "Gateway port", status, detail));
+
+    let reach = format!(
+        "curl -fsS -o /dev/null -m 10 {url} 2>/dev/null || wget -q -O /dev/null -T 10 {url}",
+        url = NPM_REGISTRY
+    );
+    checks.push(match probe(&reach) {
+        Some(_) => check("npm_registry", "npm registry", CheckStatus::Pass, "registry.npmjs.org is reachable"),
+        None => check(
+            "npm_registry",
+            "npm registry",
+            CheckStatus::Fail,
+            "Cannot reach registry.npmjs.org; check the server's outbound network or proxy settings",
+        ),
+    });
+
+    let status = checks.iter().map(|c| c.status).max().unwrap_or(CheckStatus::Pass);
+    PreflightReport { status, checks }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preflight_parsing_and_grading() {
+        assert_eq!(parse_node_major("v22.11.0\n"), Some(22));
+        assert_eq!(parse_node_major("v18.19.1"), Some(18));
+        assert_eq!(grade_node(Some("v22.11.0")).0, CheckStatus::Pass);
+        assert_eq!(grade_node(Some("v18.19.1")).0, CheckStatus::Fail);
+        assert_eq!(grade_node(None).0, CheckStatus::Warn);
+
+        let df = "Filesystem     1024-blocks     Used Available Capacity Mounted on\n/dev/sda1         41152736 12345678  28807058      31% /\n";
+        assert_eq!(parse_df_available_kb(df), Some(28807058));
+        assert_eq!(grade_disk(512).0, CheckStatus::Fail);
+        assert_eq!(grade_disk(2048).0, CheckStatus::Warn);
+        assert_eq!(grade_disk(28807058 / 1024).0, CheckStatus::Pass);
+
+        assert_eq!(parse_total_ram_mb("MemTotal:        8039704 kB\nMemFree:  123 kB"), Some(7851));
+        assert_eq!(parse_total_ram_mb("17179869184\n"), Some(16384));
+        assert_eq!(grade_ram(256).0, CheckStatus::Fail);
+        assert_eq!(grade_ram(1024).0, CheckStatus::Warn);
+        assert_eq!(grade_ram(7851).0, CheckStatus::Pass);
+
+        assert_eq!(grade_arch("x86_64").0, CheckStatus::Pass);
+        assert_eq!(grade_arch("arm64").0, CheckStatus::Pass);
+        assert_eq!(grade_arch("armv7l").0, CheckStatus::Warn);
+        assert_eq!(grade_arch("i686").0, CheckStatus::Fail);
+
+        let ss = "State  Recv-Q Send-Q Local Address:Port Peer Address:Port\nLISTEN 0      511        127.0.0.1:18789      0.0.0.0:*\n";
+        assert!(port_is_listening(ss, 18789));
+        assert!(!port_is_listening(ss, 8789));
+        let bsd = "tcp4       0      0  127.0.0.1.18789        *.*                    LISTEN\ntcp4 0 0 10.0.0.2.52311 1.2.3.4.18789 ESTABLISHED\n";
+        assert!(port_is_listening(bsd, 18789));
+        assert!(!port_is_listening("tcp4 0 0 10.0.0.2.52311 1.2.3.4.18789 ESTABLISHED", 18789));
+        assert_eq!(grade_port(18789, None).0, CheckStatus::Pass);
+        assert_eq!(grade_port(18789, Some(true)).0, CheckStatus::Warn);
+        assert_eq!(grade_port(18789, Some(false)).0, CheckStatus::Fail);
+    }
+}
@@ -0,0 +1,349 @@
+// Facts about a remote Linux host that decide how we provision it: which
+// distribution it runs, which package manager installs Node.js there, and
+// whether we can become root at all.
+
+use crate::{execute_ssh_input, execute_ssh_timeout, shell_quote, PROBE_TIMEOUT};
+use ssh2::Session;
+
+// NodeSource setup scripts for the Node.js major we require.
+const NODESOURCE_DEB_SETUP: &str = "https://deb.nodesource.com/setup_22.x";
+const NODESOURCE_RPM_SETUP: &str = "https://rpm.nodesource.com/setup_22.x";
+const NVM_INSTALL_SCRIPT: &str = "https://raw.githubusercontent.com/nvm-sh/nvm/v0.40.1/install.sh";
+
+/// The fields of /etc/os-release we care about.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct OsRelease {
+    pub(crate) id: String,
+    pub(crate) id_like: Vec<String>,
+    pub(crate) version_id: String,
+    pub(crate) pretty_name: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum DistroFamily {
+    Debian,
+    RedHat,
+    Alpine,
+    Arch,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum PackageManager {
+    Apt,
+    Dnf,
+    Yum,
+    Apk,
+    Pacman,
+}
+
+/// One step of a Node.js install, with a label for the error if it fails.
+#[derive(Debug, PartialEq)]
+pub(crate) struct InstallCommand {
+    pub(crate) cmd: String,
+    pub(crate) what: &'static str,
+}
+
+/// Parse os-release(5): KEY=value lines, values optionally quoted.
+pub(crate) fn parse_os_release(text: &str) -> OsRelease {
+    let mut release = OsRelease::default();
+    for line in text.lines() {
+        let Some((key, value)) = line.trim().split_once('=') else { continue };
+        let value = value.trim().trim_matches(|c| c == '"' || c == '\'').to_string();
+        match key {
+            "ID" => release.id = value.to_lowercase(),
+            "ID_LIKE" => release.id_like = value.split_whitespace().map(|s| s.to_lowercase()).collect(),
+            "VERSION_ID" => release.version_id = value,
+            "PRETTY_NAME" => release.pretty_name = value,
+            _ => {}
+        }
+    }
+    release
+}
+
+impl OsRelease {
+    /// The distribution family, from ID first and then ID_LIKE.
+    pub(crate) fn family(&self) -> Option<DistroFamily> {
+        std::iter::once(&self.id).chain(self.id_like.iter()).find_map(|id| match id.as_str() {
+            "debian" | "ubuntu" | "raspbian" | "linuxmint" | "pop" => Some(DistroFamily::Debian),
+            "rhel" | "fedora" | "centos" | "rocky" | "almalinux" | "amzn" | "ol" => Some(DistroFamily::RedHat),
+            "alpine" => Some(DistroFamily::Alpine),
+            "arch" | "manjaro" | "endeavouros" => Some(DistroFamily::Arch),
+            _ => None,
+        })
+    }
+
+    pub(crate) fn display_name(&self) -> String {
+        if !self.pretty_name.is_empty() {
+            self.pretty_name.clone()
+        } else if !self.id.is_empty() {
+            format!("{} {}", self.id, self.version_id).trim().to_string()
+        } else {
+            "unknown".to_string()
+        }
+    }
+}
+
+impl PackageManager {
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            PackageManager::Apt => "apt",
+            PackageManager::Dnf => "dnf",
+            PackageManager::Yum => "yum",
+            PackageManager::Apk => "apk",
+            PackageManager::Pacman => "pacman",
+        }
+    }
+
+    /// Commands that install curl and Node.js 22 (with npm). Each one must run as
+    /// root, see `Privilege::as_root`.
+    pub(crate) fn node_install_commands(&self) -> Vec<InstallCommand> {
+        match self {
+            PackageManager::Apt => vec![
+                InstallCommand {
+                    // We chain apt-get update to ensure we can install curl
+                    cmd: "apt-get update && apt-get install -y curl".to_string(),
+                    what: "install curl",
+                },
+                InstallCommand {
+                    cmd: format!("curl -fsSL {} | bash -", NODESOURCE_DEB_SETUP),
+                    what: "set up NodeSource",
+                },
+                InstallCommand { cmd: "apt-get install -y nodejs".to_string(), what: "install Node.js" },
+            ],
+            PackageManager::Dnf | PackageManager::Yum => {
+                let pm = self.name();
+                vec![
+                    InstallCommand {
+                        // Fedora and Amazon Linux ship curl-minimal, which conflicts with the curl package
+                        cmd: format!("command -v curl >/dev/null || {} install -y curl", pm),
+                        what: "install curl",
+                    },
+                    InstallCommand {
+                        cmd: format!("curl -fsSL {} | bash -", NODESOURCE_RPM_SETUP),
+                        what: "set up NodeSource",
+                    },
+                    InstallCommand { cmd: format!("{} install -y nodejs", pm), what: "install Node.js" },
+                ]
+            }
+            PackageManager::Apk => vec![
+                InstallCommand { cmd: "apk add --no-cache curl".to_string(), what: "install curl" },
+                InstallCommand {
+                    // Older Alpine releases ship an older `nodejs`; `nodejs-current` tracks the latest major
+                    cmd: "apk add --no-cache nodejs npm && \
+                          node -e 'process.exit(parseInt(process.versions.node) >= 22 ? 0 : 1)' || \
+                          apk add --no-cache nodejs-current npm"
+                        .to_string(),
+                    what: "install Node.js",
+                },
+            ],
+            PackageManager::Pacman => vec![InstallCommand {
+                cmd: "pacman -Sy --noconfirm --needed curl nodejs npm".to_string(),
+                what: "install Node.js",
+            }],
+        }
+    }
+}
+
+/// Commands that install Node.js 22 through nvm in the user's home, for hosts where
+/// we cannot become root. Falls back to wget when curl is missing.
+pub(crate) fn nvm_node_install_commands() -> Vec<InstallCommand> {
+    vec![
+        InstallCommand {
+            cmd: format!("(curl -fsSL {url} || wget -qO- {url}) | bash", url = NVM_INSTALL_SCRIPT),
+            what: "install nvm",
+        },
+        InstallCommand {
+            cmd: "export NVM_DIR=\"$HOME/.nvm\"; . \"$NVM_DIR/nvm.sh\" && nvm install 22 && nvm alias default 22".to_string(),
+            what: "install Node.js with nvm",
+        },
+    ]
+}
+
+/// Point npm's global prefix at ~/.npm-global and put its bin dir on PATH for future
+/// logins, so `npm install -g` works without root. Node from nvm already installs
+/// globals under ~/.nvm (and nvm refuses a custom prefix), so it is left alone.
+pub(crate) const USER_NPM_PREFIX_SETUP: &str = "case \"$(command -v node)\" in \
+    */.nvm/*) ;; \
+    *) mkdir -p \"$HOME/.npm-global\" && npm config set prefix \"$HOME/.npm-global\" && \
+       (grep -qs '.npm-global/bin' \"$HOME/.profile\" || echo 'export PATH=\"$HOME/.npm-global/bin:$PATH\"' >> \"$HOME/.profile\") ;; \
+    esac";
+
+/// How the SSH user can run commands as root.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum SudoMode {
+    // Logged in as root
+    Root,
+    // `sudo -n true` succeeds
+    Passwordless,
+    // sudo with the password the user supplied
+    Password,
+    // No usable sudo: install into the user's home instead
+    Unavailable,
+}
+
+/// Root access on a remote host. Holds the sudo password in memory only; it is
+/// written to sudo's stdin (`sudo -S`) and never put on a command line.
+pub(crate) struct Privilege {
+    pub(crate) mode: SudoMode,
+    password: Option<String>,
+}
+
+impl Privilege {
+    pub(crate) fn new(mode: SudoMode, password: Option<String>) -> Privilege {
+        Privilege { mode, password }
+    }
+
+    /// The command line and stdin that run `cmd` as root, or `None` without root access.
+    pub(crate) fn as_root(&self, cmd: &str) -> Option<(String, Option<String>)> {
+        match self.mode {
+            SudoMode::Root => Some((cmd.to_string(), None)),
+            SudoMode::Passwordless => Some((format!("sudo -n sh -c {}", shell_quote(cmd)), None)),
+            SudoMode::Password => Some((
+                format!("sudo -S -p '' sh -c {}", shell_quote(cmd)),
+                self.password.as_ref().map(|p| format!("{}\n", p)),
+            )),
+            SudoMode::Unavailable => None,
+        }
+    }
+}
+
+/// Work out whether (and how) we can become root on the remote host.
+/// A supplied sudo password that sudo rejects is an error rather than a silent downgrade.
+pub(crate) fn detect_privilege(sess: &Session, sudo_password: Option<&str>) -> Result<Privilege, String> {
+    let id = execute_ssh_timeout(sess, "id -u", PROBE_TIMEOUT)?;
+    if id.trim() == "0" {
+        return Ok(Privilege::new(SudoMode::Root, None));
+    }
+    if execute_ssh_timeout(sess, "sudo -n true", PROBE_TIMEOUT).is_ok() {
+        return Ok(Privilege::new(SudoMode::Passwordless, None));
+    }
+    match sudo_password.filter(|p| !p.is_empty()) {
+        Some(password) => {
+            let input = format!("{}\n", password);
+            execute_ssh_input(sess, "sudo -S -p '' -v", Some(&input), PROBE_TIMEOUT)
+                .map_err(|_| "The sudo password was rejected by the remote server.".to_string())?;
+            Ok(Privilege::new(SudoMode::Password, Some(password.to_string())))
+        }
+        None => Ok(Privilege::new(SudoMode::Unavailable, None)),
+    }
+}
+
+/// The command line and stdin for `npm <args>` on global packages. Runs as the user when
+/// the global prefix is writable (nvm, Homebrew or a user prefix), as root when we can
+/// become root, and otherwise after moving the prefix into the user's home.
+/// `env` is the shell prefix that puts node on PATH (see `get_env_prefix`).
+pub(crate) fn npm_global_command(sess: &Session, privilege: &Privilege, env: &str, args: &str) -> (String, Option<String>) {
+    let writable = execute_ssh_timeout(sess, &format!("{}[ -w \"$(npm root -g)\" ]", env), PROBE_TIMEOUT).is_ok();
+    npm_global_plan(privilege, writable, env, args)
+}
+
+pub(crate) fn npm_global_plan(privilege: &Privilege, prefix_writable: bool, env: &str, args: &str) -> (String, Option<String>) {
+    let npm = format!("{}npm {}", env, args);
+    if prefix_writable {
+        return (npm, None);
+    }
+    privilege
+        .as_root(&npm)
+        .unwrap_or_else(|| (format!("{}{} && npm {}", env, USER_NPM_PREFIX_SETUP, args), None))
+}
+
+/// Read and parse the remote host's os-release file.
+pub(crate) fn read_os_release(sess: &Session) -> Result<OsRelease, String> {
+    let text = execute_ssh_timeout(sess, "cat /etc/os-release 2>/dev/null || cat /usr/lib/os-release", PROBE_TIMEOUT)
+        .map_err(|_| "Could not read /etc/os-release on the remote server".to_string())?;
+    Ok(parse_os_release(&text))
+}
+
+/// Work out which package manager to install Node.js with on a remote Linux host.
+pub(crate) fn detect_package_manager(sess: &Session) -> Result<PackageManager, String> {
+    let release = read_os_release(sess)?;
+    let has = |bin: &str| execute_ssh_timeout(sess, &format!("command -v {}", bin), PROBE_TIMEOUT).is_ok();
+
+    match release.family() {
+        Some(DistroFamily::Debian) => Ok(PackageManager::Apt),
+        // Newer releases use dnf; CentOS 7 and Amazon Linux 2 only have yum
+        Some(DistroFamily::RedHat) if has("dnf") => Ok(PackageManager::Dnf),
+        Some(DistroFamily::RedHat) if has("yum") => Ok(PackageManager::Yum),
+        Some(DistroFamily::Alpine) => Ok(PackageManager::Apk),
+        Some(DistroFamily::Arch) => Ok(PackageManager::Pacman),
+        _ => Err(format!(
+            "Unsupported Linux distribution: {}. Supported distributions are Debian/Ubuntu (apt), \
+             Fedora/RHEL/Rocky/AlmaLinux/Amazon Linux (dnf or yum), Alpine (apk) and Arch (pacman).",
+            release.display_name()
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_os_release_maps_to_distro_family() {
+        let rocky = parse_os_release(
+            "NAME=\"Rocky Linux\"\nID=\"rocky\"\nID_LIKE=\"rhel centos fedora\"\nVERSION_ID=\"9.3\"\nPRETTY_NAME=\"Rocky Linux 9.3 (Blue Onyx)\"\n",
+        );
+        assert_eq!(rocky.id, "rocky");
+        assert_eq!(rocky.id_like, vec!["rhel", "centos", "fedora"]);
+        assert_eq!(rocky.family(), Some(DistroFamily::RedHat));
+        assert_eq!(rocky.display_name(), "Rocky Linux 9.3 (Blue Onyx)");
+
+        let families = [
+            ("ID=ubuntu\nID_LIKE=debian", Some(DistroFamily::Debian)),
+            ("ID=amzn\nID_LIKE=\"centos rhel fedora\"", Some(DistroFamily::RedHat)),
+            ("ID=alpine\nVERSION_ID=3.20.0", Some(DistroFamily::Alpine)),
+            ("ID=arch", Some(DistroFamily::Arch)),
+            ("ID=opensuse-leap\nID_LIKE=\"suse opensuse\"", None),
+        ];
+        for (text, family) in families {
+            assert_eq!(parse_os_release(text).family(), family, "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_node_install_commands_per_package_manager() {
+        let apt = PackageManager::Apt.node_install_commands();
+        assert_eq!(apt.len(), 3);
+        assert_eq!(apt[1].cmd, "curl -fsSL https://deb.nodesource.com/setup_22.x | bash -");
+
+        let dnf = PackageManager::Dnf.node_install_commands();
+        assert_eq!(dnf[1].cmd, "curl -fsSL https://rpm.nodesource.com/setup_22.x | bash -");
+        assert_eq!(dnf[2].cmd, "dnf install -y nodejs");
+        assert_eq!(PackageManager::Yum.node_install_commands()[2].cmd, "yum install -y nodejs");
+
+        let apk = PackageManager::Apk.node_install_commands();
+        assert!(apk[1].cmd.contains("nodejs-current"));
+
+        let pacman = PackageManager::Pacman.node_install_commands();
+        assert_eq!(pacman[0].cmd, "pacman -Sy --noconfirm --needed curl nodejs npm");
+    }
+
+    #[test]
+    fn test_sudo_password_only_goes_to_stdin() {
+        let cmd = "apt-get install -y nodejs";
+        let root = Privilege::new(SudoMode::Root, None);
+        assert_eq!(root.as_root(cmd), Some((cmd.to_string(), None)));
+
+        let passwordless = Privilege::new(SudoMode::Passwordless, None);
+        assert_eq!(passwordless.as_root(cmd), Some(("sudo -n sh -c 'apt-get install -y nodejs'".to_string(), None)));
+
+        let with_password = Privilege::new(SudoMode::Password, Some("s3cret".to_string()));
+        let (line, input) = with_password.as_root(cmd).unwrap();
+        assert_eq!(line, "sudo -S -p '' sh -c 'apt-get install -y nodejs'");
+        assert!(!line.contains("s3cret"));
+        assert_eq!(input.as_deref(), Some("s3cret\n"));
+
+        let none = Privilege::new(SudoMode::Unavailable, None);
+        assert_eq!(none.as_root(cmd), None);
+
+        // npm: a writable prefix needs no root; without root the prefix moves to ~/.npm-global
+        assert_eq!(npm_global_plan(&none, true, "", "install -g openclaw").0, "npm install -g openclaw");
+        let (user_install, input) = npm_global_plan(&none, false, "", "install -g openclaw");
+        assert!(user_install.contains("npm config set prefix \"$HOME/.npm-global\""));
+        assert!(user_install.ends_with("&& npm install -g openclaw"));
+        assert_eq!(input, None);
+        let (root_install, input) = npm_global_plan(&with_password, false, "", "uninstall -g openclaw");
+        assert_eq!(root_install, "sudo -S -p '' sh -c 'npm uninstall -g openclaw'");
+        assert_eq!(input.as_deref(), Some("s3cret\n"));
+    }
+}
@@ -0,0 +1,390 @@
+// Keeping the OpenClaw gateway running on a remote host.
+//
+// On hosts with a systemd user manager the gateway runs as the
+// `openclaw-gateway.service` user unit that `openclaw gateway install` sets up.
+// User units stop when the user's last session closes unless lingering is
+// enabled, so health reports include the Linger flag. On macOS the same
+// install writes a LaunchAgent, which we bootstrap into the user's launchd
+// domain ourselves because `openclaw gateway start` cannot find an unloaded
+// one. Hosts without a user manager (containers, WSL without systemd) get a
+// plain supervisor loop that restarts the gateway when it exits; it does not
+// survive a reboot.
+
+use crate::remote_host::Privilege;
+use crate::{execute_ssh, execute_ssh_input, execute_ssh_streaming, execute_ssh_timeout, shell_quote, InstallLog, DEFAULT_COMMAND_TIMEOUT, PROBE_TIMEOUT};
+use ssh2::Session;
+use std::collections::HashMap;
+
+const GATEWAY_UNIT: &str = "openclaw-gateway.service";
+const LAUNCHD_LABEL: &str = "ai.openclaw.gateway";
+const LAUNCHD_PLIST: &str = "Library/LaunchAgents/ai.openclaw.gateway.plist";
+// Picks the launchd domain: gui/<uid> when the user has a GUI login (the usual
+// case for an auto-login Mac mini), otherwise the background user/<uid> domain.
+const LAUNCHD_DOMAIN: &str = "d=\"gui/$(id -u)\"; launchctl print \"$d\" >/dev/null 2>&1 || d=\"user/$(id -u)\"; ";
+// Supervisor state, relative to the remote home directory
+const SUPERVISOR_PID_FILE: &str = ".openclaw/gateway-supervisor.pid";
+const SUPERVISOR_LOG_FILE: &str = ".openclaw/logs/gateway.log";
+// Lines of journal or log output included in a health report
+const LOG_LINES: usize = 50;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ServiceManager {
+    // `systemctl --user` works
+    Systemd,
+    // macOS LaunchAgent
+    Launchd,
+    // No user service manager: a background shell loop keeps the gateway up
+    Supervised,
+}
+
+impl ServiceManager {
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            ServiceManager::Systemd => "systemd",
+            ServiceManager::Launchd => "launchd",
+            ServiceManager::Supervised => "supervised",
+        }
+    }
+}
+
+/// The properties of `systemctl show` we report.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct UnitState {
+    pub(crate) load_state: String,
+    pub(crate) active_state: String,
+    pub(crate) sub_state: String,
+    pub(crate) main_pid: Option<u32>,
+}
+
+/// A loaded LaunchAgent as `launchctl print` describes it.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct LaunchdState {
+    // "running", "not running", ...
+    pub(crate) state: String,
+    pub(crate) pid: Option<u32>,
+    pub(crate) last_exit: Option<String>,
+    pub(crate) stdout_path: Option<String>,
+    pub(crate) stderr_path: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ServiceHealth {
+    // "systemd", "launchd" or "supervised"
+    manager: String,
+    // Whether the user's services outlive their sessions. None when not applicable or unknown.
+    linger: Option<bool>,
+    running: bool,
+    // systemd ActiveState/SubState; "active"/"inactive" plus the launchd state or
+    // supervisor state otherwise
+    active_state: String,
+    sub_state: String,
+    // Most recent journal (systemd) or log file (supervisor) lines, oldest first
+    logs: Vec<String>,
+}
+
+/// Parse `KEY=value` lines as printed by `systemctl show` and `loginctl show-user`.
+fn parse_properties(text: &str) -> HashMap<&str, &str> {
+    text.lines().filter_map(|line| line.trim().split_once('=')).collect()
+}
+
+pub(crate) fn parse_unit_state(text: &str) -> UnitState {
+    let props = parse_properties(text);
+    let get = |key: &str| props.get(key).map(|v| v.to_string()).unwrap_or_default();
+    UnitState {
+        load_state: get("LoadState"),
+        active_state: get("ActiveState"),
+        sub_state: get("SubState"),
+        // systemd reports MainPID=0 when nothing is running
+        main_pid: props.get("MainPID").and_then(|p| p.parse().ok()).filter(|&p| p != 0),
+    }
+}
+
+/// The parts of `launchctl print <domain>/<label>` we report. Nested dictionaries
+/// repeat some keys, so only the service's own top-level entries count.
+pub(crate) fn parse_launchd_print(text: &str) -> LaunchdState {
+    let mut state = LaunchdState::default();
+    for line in text.lines() {
+        // Top-level properties are indented by exactly one tab
+        let Some(line) = line.strip_prefix('\t').filter(|l| !l.starts_with('\t')) else { continue };
+        let Some((key, value)) = line.split_once(" = ") else { continue };
+        match key.trim() {
+            "state" => state.state = value.trim().to_string(),
+            "pid" => state.pid = value.trim().parse().ok(),
+            "last exit code" => state.last_exit = Some(value.trim().to_string()),
+            "stdout path" => state.stdout_path = Some(value.trim().to_string()),
+            "stderr path" => state.stderr_path = Some(value.trim().to_string()),
+            _ => {}
+        }
+    }
+    state
+}
+
+pub(crate) fn parse_linger(text: &str) -> Option<bool> {
+    match parse_properties(text).get("Linger") {
+        Some(&"yes") => Some(true),
+        Some(&"no") => Some(false),
+        _ => None,
+    }
+}
+
+/// Which service manager runs the gateway for the remote user: launchd on macOS,
+/// the systemd user manager when we can talk to it, otherwise our supervisor.
+pub(crate) fn detect_service_manager(sess: &Session) -> ServiceManager {
+    let os = execute_ssh_timeout(sess, "uname -s", PROBE_TIMEOUT).unwrap_or_default();
+    if os.trim() == "Darwin" {
+        return ServiceManager::Launchd;
+    }
+    match execute_ssh_timeout(sess, "systemctl --user show-environment >/dev/null", PROBE_TIMEOUT) {
+        Ok(_) => ServiceManager::Systemd,
+        Err(_) => ServiceManager::Supervised,
+    }
+}
+
+/// Load the gateway LaunchAgent into the user's domain if it is not loaded yet.
+/// Without this `openclaw gateway start` fails with "Could not find service".
+fn launchd_bootstrap(sess: &Session) -> Result<(), String> {
+    let cmd = format!(
+        "{domain}[ -f \"$HOME/{plist}\" ] || exit 0; \
+         launchctl print \"$d/{label}\" >/dev/null 2>&1 || launchctl bootstrap \"$d\" \"$HOME/{plist}\"",
+        domain = LAUNCHD_DOMAIN,
+        plist = LAUNCHD_PLIST,
+        label = LAUNCHD_LABEL
+    );
+    execute_ssh(sess, &cmd).map_err(|e| format!("Failed to load the gateway LaunchAgent: {}", e))?;
+    Ok(())
+}
+
+fn launchd_state(sess: &Session) -> Option<LaunchdState> {
+    let cmd = format!("{}launchctl print \"$d/{}\"", LAUNCHD_DOMAIN, LAUNCHD_LABEL);
+    execute_ssh_timeout(sess, &cmd, PROBE_TIMEOUT).ok().map(|out| parse_launchd_print(&out))
+}
+
+/// The Linger flag for the SSH user, or None if logind cannot tell us.
+pub(crate) fn linger_enabled(sess: &Session) -> Option<bool> {
+    execute_ssh_timeout(sess, "loginctl show-user \"$(id -un)\" --property=Linger", PROBE_TIMEOUT)
+        .ok()
+        .and_then(|out| parse_linger(&out))
+}
+
+/// Enable lingering for the SSH user. logind usually lets users enable it for
+/// themselves; otherwise it takes root.
+pub(crate) fn enable_linger(sess: &Session, privilege: &Privilege) -> Result<(), String> {
+    if execute_ssh_timeout(sess, "loginctl enable-linger \"$(id -un)\"", PROBE_TIMEOUT).is_ok() {
+        return Ok(());
+    }
+    let user = execute_ssh_timeout(sess, "id -un", PROBE_TIMEOUT)?.trim().to_string();
+    let (cmd, input) = privilege
+        .as_root(&format!("loginctl enable-linger {}", shell_quote(&user)))
+        .ok_or("Enabling lingering needs root access. Ask an administrator to run `loginctl enable-linger` for this user.")?;
+    execute_ssh_input(sess, &cmd, input.as_deref(), PROBE_TIMEOUT)?;
+    Ok(())
+}
+
+/// Shell command that starts the supervisor loop detached from the SSH session.
+/// `env` puts openclaw on PATH (see `get_env_prefix`). The gateway runs in the
+/// foreground and takes its port and bind address from openclaw.json.
+pub(crate) fn supervisor_start_command(env: &str) -> String {
+    let supervisor = format!(
+        "echo $$ > \"$HOME/{pid}\"; while :; do {env}openclaw gateway >> \"$HOME/{log}\" 2>&1; sleep 2; done",
+        pid = SUPERVISOR_PID_FILE,
+        log = SUPERVISOR_LOG_FILE,
+        env = env
+    );
+    // setsid gives the loop its own process group, so stopping it takes the gateway along
+    // and our own timeout handling (which kills the command's group) leaves it alone
+    format!(
+        "mkdir -p \"$HOME/.openclaw/logs\" && nohup setsid sh -c {} >/dev/null 2>&1 </dev/null &",
+        shell_quote(&supervisor)
+    )
+}
+
+fn supervisor_stop_command() -> String {
+    format!(
+        "f=\"$HOME/{}\"; [ -f \"$f\" ] && kill -TERM -- -\"$(cat \"$f\")\" 2>/dev/null; rm -f \"$f\"; true",
+        SUPERVISOR_PID_FILE
+    )
+}
+
+fn supervisor_running(sess: &Session) -> bool {
+    let check = format!("kill -0 \"$(cat \"$HOME/{}\" 2>/dev/null)\" 2>/dev/null", SUPERVISOR_PID_FILE);
+    execute_ssh_timeout(sess, &check, PROBE_TIMEOUT).is_ok()
+}
+
+/// Stop the gateway, whichever way it was started.
+pub(crate) fn stop_gateway(sess: &Session, env: &str) {
+    let _ = execute_ssh(sess, &supervisor_stop_command());
+    let _ = execute_ssh(sess, &format!("{}openclaw gateway stop || true", env));
+}
+
+/// (Re)start the gateway under `manager`, streaming output through `log` when given.
+pub(crate) fn start_gateway(sess: &Session, env: &str, manager: ServiceManager, log: Option<&InstallLog>) -> Result<(), String> {
+    stop_gateway(sess, env);
+    match manager {
+        ServiceManager::Systemd => {
+            // Reset any failed systemd state from crash-loops before starting
+            let _ = execute_ssh(sess, &format!("systemctl --user reset-failed {} 2>/dev/null || true", GATEWAY_UNIT));
+            let start = format!("{}openclaw gateway start", env);
+            match log {
+                Some(log) => execute_ssh_streaming(sess, &start, log)?,
+                None => execute_ssh_timeout(sess, &start, DEFAULT_COMMAND_TIMEOUT)?,
+            };
+        }
+        ServiceManager::Launchd => {
+            launchd_bootstrap(sess)?;
+            let start = format!("{}openclaw gateway start", env);
+            match log {
+                Some(log) => execute_ssh_streaming(sess, &start, log)?,
+                None => execute_ssh_timeout(sess, &start, DEFAULT_COMMAND_TIMEOUT)?,
+            };
+        }
+        ServiceManager::Supervised => {
+            if let Some(log) = log {
+                log.line("stdout", "No systemd user manager found, running the gateway under a background supervisor");
+            }
+            execute_ssh(sess, &supervisor_start_command(env))?;
+        }
+    }
+    Ok(())
+}
+
+/// Restart a running gateway, e.g. after an update. launchd restarts the loaded
+/// agent in place; elsewhere this is a stop and start.
+pub(crate) fn restart_gateway(sess: &Session, env: &str, manager: ServiceManager) -> Result<(), String> {
+    if manager == ServiceManager::Launchd && launchd_state(sess).is_some() {
+        let kickstart = format!("{}launchctl kickstart -k \"$d/{}\"", LAUNCHD_DOMAIN, LAUNCHD_LABEL);
+        execute_ssh(sess, &kickstart).map_err(|e| format!("Failed to restart the gateway LaunchAgent: {}", e))?;
+        return Ok(());
+    }
+    start_gateway(sess, env, manager, None)
+}
+
+/// Whether the gateway is up under `manager`.
+pub(crate) fn gateway_running(sess: &Session, manager: ServiceManager) -> bool {
+    match manager {
+        ServiceManager::Systemd => {
+            let cmd = format!("systemctl --user is-active --quiet {}", GATEWAY_UNIT);
+            execute_ssh_timeout(sess, &cmd, PROBE_TIMEOUT).is_ok()
+        }
+        ServiceManager::Launchd => launchd_state(sess).is_some_and(|s| s.state == "running"),
+        ServiceManager::Supervised => supervisor_running(sess),
+    }
+}
+
+/// Service state, lingering and recent log lines for the remote gateway.
+pub(crate) fn service_health(sess: &Session) -> ServiceHealth {
+    let manager = detect_service_manager(sess);
+    match manager {
+        ServiceManager::Systemd => {
+            let show = format!("systemctl --user show {} --property=LoadState,ActiveState,SubState,MainPID", GATEWAY_UNIT);
+            let state = execute_ssh_timeout(sess, &show, PROBE_TIMEOUT).map(|s| parse_unit_state(&s)).unwrap_or_default();
+            let journal = format!("journalctl --user -u {} -n {} --no-pager -o short-iso 2>&1", GATEWAY_UNIT, LOG_LINES);
+            ServiceHealth {
+                manager: manager.name().to_string(),
+                linger: linger_enabled(sess),
+                running: state.active_state == "active",
+                active_state: state.active_state,
+                sub_state: state.sub_state,
+                logs: tail_lines(execute_ssh_timeout(sess, &journal, PROBE_TIMEOUT).unwrap_or_default()),
+            }
+        }
+        ServiceManager::Launchd => {
+            let state = launchd_state(sess);
+            let running = state.as_ref().is_some_and(|s| s.state == "running");
+            let sub_state = match &state {
+                Some(s) if s.state != "running" => match &s.last_exit {
+                    Some(code) => format!("{} (last exit code {})", s.state, code),
+                    None => s.state.clone(),
+                },
+                Some(s) => s.state.clone(),
+                None => "not loaded".to_string(),
+            };
+            // The LaunchAgent names its own log files; fall back to where openclaw puts them
+            let logs = state
+                .as_ref()
+                .map(|s| [s.stdout_path.clone(), s.stderr_path.clone()].into_iter().flatten().collect::<Vec<_>>())
+                .filter(|paths| !paths.is_empty())
+                .unwrap_or_else(|| vec![format!("$HOME/{}", SUPERVISOR_LOG_FILE)]);
+            let files: Vec<String> = logs.iter().map(|p| if p.starts_with('$') { format!("\"{}\"", p) } else { shell_quote(p) }).collect();
+            // A missing file makes tail fail even when the other one was printed
+            let tail = format!("tail -q -n {} {} 2>/dev/null; true", LOG_LINES, files.join(" "));
+            ServiceHealth {
+                manager: manager.name().to_string(),
+                // LaunchAgents belong to the login session; there is no lingering on macOS
+                linger: None,
+                running,
+                active_state: if running { "active" } else { "inactive" }.to_string(),
+                sub_state,
+                logs: tail_lines(execute_ssh_timeout(sess, &tail, PROBE_TIMEOUT).unwrap_or_default()),
+            }
+        }
+        ServiceManager::Supervised => {
+            let running = supervisor_running(sess);
+            let tail = format!("tail -n {} \"$HOME/{}\" 2>/dev/null", LOG_LINES, SUPERVISOR_LOG_FILE);
+            ServiceHealth {
+                manager: manager.name().to_string(),
+                linger: None,
+                running,
+                active_state: if running { "active" } else { "inactive" }.to_string(),
+                sub_state: if running { "running" } else { "dead" }.to_string(),
+                logs: tail_lines(execute_ssh_timeout(sess, &tail, PROBE_TIMEOUT).unwrap_or_default()),
+            }
+        }
+    }
+}
+
+fn tail_lines(text: String) -> Vec<String> {
+    text.lines().map(str::to_string).filter(|l| !l.trim().is_empty()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remote_service_state_parsing() {
+        let state = parse_unit_state("LoadState=loaded\nActiveState=active\nSubState=running\nMainPID=4242\n");
+        assert_eq!(state.load_state, "loaded");
+        assert_eq!(state.active_state, "active");
+        assert_eq!(state.sub_state, "running");
+        assert_eq!(state.main_pid, Some(4242));
+        assert_eq!(parse_unit_state("ActiveState=inactive\nMainPID=0").main_pid, None);
+
+        assert_eq!(parse_linger("Linger=yes\n"), Some(true));
+        assert_eq!(parse_linger("Linger=no"), Some(false));
+        assert_eq!(parse_linger("Failed to get user: No such user"), None);
+
+        // The supervisor detaches into its own session and restarts the gateway when it exits
+        let cmd = supervisor_start_command("export PATH=\"$PATH\"; ");
+        assert!(cmd.starts_with("mkdir -p \"$HOME/.openclaw/logs\" && nohup setsid sh -c '"));
+        assert!(cmd.contains("while :; do export PATH="));
+        assert!(cmd.ends_with(">/dev/null 2>&1 </dev/null &"));
+    }
+
+    #[test]
+    fn test_launchd_print_parsing() {
+        let running = "gui/501/ai.openclaw.gateway = {\n\
+            \tactive count = 1\n\
+            \tpath = /Users/mini/Library/LaunchAgents/ai.openclaw.gateway.plist\n\
+            \tstate = running\n\
+            \n\
+            \tstdout path = /Users/mini/.openclaw/logs/gateway.log\n\
+            \tstderr path = /Users/mini/.openclaw/logs/gateway.err.log\n\
+            \tpid = 812\n\
+            \tlast exit code = (never exited)\n\
+            \tsockets = {\n\
+            \t\tstate = inactive\n\
+            \t}\n\
+            }\n";
+        let state = parse_launchd_print(running);
+        assert_eq!(state.state, "running");
+        assert_eq!(state.pid, Some(812));
+        assert_eq!(state.stdout_path.as_deref(), Some("/Users/mini/.openclaw/logs/gateway.log"));
+        assert_eq!(state.stderr_path.as_deref(), Some("/Users/mini/.openclaw/logs/gateway.err.log"));
+
+        let stopped = parse_launchd_print("gui/501/ai.openclaw.gateway = {\n\tstate = not running\n\tlast exit code = 78: EX_CONFIG\n}\n");
+        assert_eq!(stopped.state, "not running");
+        assert_eq!(stopped.pid, None);
+        assert_eq!(stopped.last_exit.as_deref(), Some("78: EX_CONFIG"));
+    }
+}
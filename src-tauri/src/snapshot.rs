@@ -0,0 +1,277 @@
+// Snapshots of ~/.openclaw taken before Clawnetes changes it, so a reconfigure,
+// repair or uninstall can be rolled back. Each snapshot is a tar.gz made by the
+// target's own tar (inside WSL on Windows, over SSH for remote hosts) and kept on
+// that target in ~/.openclaw-snapshots, outside the directory an uninstall removes.
+
+use crate::config_plan::{mask_json, unified_diff, ConfigTarget, PlannedFile};
+use crate::shell_quote;
+use std::collections::BTreeSet;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const SNAPSHOT_DIR: &str = ".openclaw-snapshots";
+// Retention: the newest snapshots kept per target. Older ones are deleted each
+// time a snapshot is taken.
+pub(crate) const KEEP_SNAPSHOTS: usize = 10;
+// What a snapshot holds, relative to ~/.openclaw: the config, Clawnetes' metadata,
+// and every agent's workspace and auth profiles. Session logs are left out.
+pub(crate) const SNAPSHOT_PATHS: &str = "openclaw.json clawnetes-meta.json workspace agents/*/agent agents/*/workspace";
+
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SnapshotInfo {
+    pub(crate) id: String,
+    // Seconds since the Unix epoch
+    pub(crate) created_at: u64,
+    // What the snapshot was taken before: "configure", "update", "skill-install",
+    // "skill-create", "workspace-edit", "uninstall", "doctor-repair", "security-fix",
+    // "import" or "restore"
+    pub(crate) reason: String,
+    pub(crate) size: u64,
+}
+
+impl SnapshotInfo {
+    /// Read a snapshot file name, `<created_at>-<millis>-<reason>.tar.gz`. Names without
+    /// the milliseconds, from before they were added, are read too.
+    fn from_file_name(name: &str, size: u64) -> Option<SnapshotInfo> {
+        let id = name.strip_suffix(".tar.gz")?;
+        let (created_at, rest) = id.split_once('-')?;
+        let reason = match rest.split_once('-') {
+            Some((millis, reason)) if millis.len() == 3 && millis.chars().all(|c| c.is_ascii_digit()) => reason,
+            _ => rest,
+        };
+        let valid_reason = !reason.is_empty() && reason.chars().all(|c| c.is_ascii_lowercase() || c == '-');
+        if !valid_reason {
+            return None;
+        }
+        Some(SnapshotInfo { id: id.to_string(), created_at: created_at.parse().ok()?, reason: reason.to_string(), size })
+    }
+}
+
+/// Run `script` with sh, whatever the login shell is; zsh, for one, fails on globs
+/// that match nothing.
+pub(crate) fn run_sh(target: &dyn ConfigTarget, script: &str) -> Result<String, String> {
+    target.run(&format!("sh -c {}", shell_quote(script)))
+}
+
+fn snapshot_dir(home: &str) -> String {
+    format!("{}/{}", home, SNAPSHOT_DIR)
+}
+
+fn snapshot_file(home: &str, id: &str) -> String {
+    format!("{}/{}.tar.gz", snapshot_dir(home), id)
+}
+
+/// Snapshot ~/.openclaw on `target` before a change described by `reason`, then
+/// apply the retention policy. Returns None when there is nothing to save yet.
+pub(crate) fn take(target: &dyn ConfigTarget, home: &str, reason: &str) -> Result<Option<SnapshotInfo>, String> {
+    let snapshot = save(target, home, reason)?;
+    if snapshot.is_some() {
+        prune(target, home, KEEP_SNAPSHOTS)?;
+    }
+    Ok(snapshot)
+}
+
+fn save(target: &dyn ConfigTarget, home: &str, reason: &str) -> Result<Option<SnapshotInfo>, String> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    let created_at = now.as_secs();
+    // The milliseconds keep snapshots taken within the same second apart
+    let id = format!("{}-{:03}-{}", created_at, now.subsec_millis(), reason);
+    let file = shell_quote(&snapshot_file(home, &id));
+    // Snapshots hold API keys and tokens, so they are readable by the owner only. An
+    // existing snapshot is never overwritten.
+    let script = format!(
+        "cd {root} 2>/dev/null || exit 0; set -- $(ls -d {paths} 2>/dev/null); [ $# -gt 0 ] || exit 0; \
+         if [ -e {file} ]; then echo 'a snapshot with this name already exists' >&2; exit 1; fi; \
+         umask 077 && mkdir -p {dir} && tar -czf {file}.tmp \"$@\" && mv {file}.tmp {file} && wc -c < {file}",
+        root = shell_quote(&format!("{}/.openclaw", home)),
+        paths = SNAPSHOT_PATHS,
+        dir = shell_quote(&snapshot_dir(home)),
+        file = file,
+    );
+    let size = run_sh(target, &script).map_err(|e| format!("Failed to snapshot ~/.openclaw: {}", e))?;
+    if size.trim().is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(SnapshotInfo { id, created_at, reason: reason.to_string(), size: size.trim().parse().unwrap_or(0) }))
+}
+
+/// The snapshots on `target`, newest first.
+pub(crate) fn list(target: &dyn ConfigTarget, home: &str) -> Result<Vec<SnapshotInfo>, String> {
+    let script = format!(
+        "cd {} 2>/dev/null || exit 0; for f in *.tar.gz; do [ -f \"$f\" ] && echo \"$(wc -c < \"$f\") $f\"; done; true",
+        shell_quote(&snapshot_dir(home))
+    );
+    Ok(parse_listing(&run_sh(target, &script)?))
+}
+
+/// Lines of "<size> <file name>", newest snapshot first. Files that are not ours are skipped.
+pub(crate) fn parse_listing(output: &str) -> Vec<SnapshotInfo> {
+    let mut snapshots: Vec<SnapshotInfo> = output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let size = fields.next()?.parse().ok()?;
+            SnapshotInfo::from_file_name(fields.next()?, size)
+        })
+        .collect();
+    snapshots.sort_by(|a, b| b.created_at.cmp(&a.created_at).then_with(|| b.id.cmp(&a.id)));
+    snapshots
+}
+
+/// Delete all but the newest `keep` snapshots.
+fn prune(target: &dyn ConfigTarget, home: &str, keep: usize) -> Result<(), String> {
+    let stale: Vec<String> =
+        list(target, home)?.iter().skip(keep).map(|s| shell_quote(&snapshot_file(home, &s.id))).collect();
+    if !stale.is_empty() {
+        target.run(&format!("rm -f {}", stale.join(" ")))?;
+    }
+    Ok(())
+}
+
+fn find(target: &dyn ConfigTarget, home: &str, id: &str) -> Result<SnapshotInfo, String> {
+    list(target, home)?.into_iter().find(|s| s.id == id).ok_or_else(|| format!("No snapshot {}", id))
+}
+
+/// What restoring snapshot `id` would change, file by file: each diff goes from the
+/// current file to the snapshot's copy. Secrets in JSON files are masked.
+pub(crate) fn diff(target: &dyn ConfigTarget, home: &str, id: &str) -> Result<Vec<PlannedFile>, String> {
+    let snapshot = find(target, home, id)?;
+    let file = shell_quote(&snapshot_file(home, &snapshot.id));
+    let root = format!("{}/.openclaw", home);
+
+    let saved: BTreeSet<String> = run_sh(target, &format!("tar -tzf {}", file))?
+        .lines()
+        .filter(|entry| !entry.ends_with('/'))
+        .map(|entry| entry.trim_start_matches("./").to_string())
+        .collect();
+    let current: BTreeSet<String> =
+        run_sh(target, &format!("cd {} 2>/dev/null && find {} -type f 2>/dev/null; true", shell_quote(&root), SNAPSHOT_PATHS))?
+            .lines()
+            .map(|path| path.trim_start_matches("./").to_string())
+            .collect();
+
+    let mut files = Vec::new();
+    for path in saved.union(&current) {
+        let now = if current.contains(path) { target.read(&format!("{}/{}", root, path))? } else { String::new() };
+        let then = if saved.contains(path) { run_sh(target, &format!("tar -xzOf {} {}", file, shell_quote(path)))? } else { String::new() };
+        let (now, then) = if path.ends_with(".json") { (mask_json(path, &now), mask_json(path, &then)) } else { (now, then) };
+        let status = if !current.contains(path) {
+            "added"
+        } else if !saved.contains(path) {
+            "removed"
+        } else if now == then {
+            "unchanged"
+        } else {
+            "modified"
+        };
+        let full_path = format!("{}/{}", root, path);
+        files.push(PlannedFile { diff: unified_diff(&full_path, &now, &then), path: full_path, status: status.to_string() });
+    }
+    Ok(files)
+}
+
+/// Put ~/.openclaw back the way snapshot `id` recorded it. The current state is
+/// snapshotted first, so a restore can itself be undone. Files the snapshot covers
+/// but did not have are removed.
+pub(crate) fn restore(target: &dyn ConfigTarget, home: &str, id: &str) -> Result<(), String> {
+    let snapshot = find(target, home, id)?;
+    // Pruned only after extracting, so restoring the oldest snapshot cannot delete it
+    save(target, home, "restore")?;
+    let script = format!(
+        "mkdir -p {root} && cd {root} && rm -rf {paths} && tar -xzf {file}",
+        root = shell_quote(&format!("{}/.openclaw", home)),
+        paths = SNAPSHOT_PATHS,
+        file = shell_quote(&snapshot_file(home, &snapshot.id)),
+    );
+    run_sh(target, &script).map_err(|e| format!("Failed to restore snapshot {}: {}", id, e))?;
+    prune(target, home, KEEP_SNAPSHOTS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{ShellTarget, TempDir};
+    use std::fs;
+
+    #[test]
+    fn test_snapshot_listing_newest_first() {
+        let listing = "120 1700000100-configure.tar.gz\n\
+                       80 1700000300-250-doctor-repair.tar.gz\n\
+                       5 notes.txt\n\
+                       9 1700000200-Bad_Reason.tar.gz\n\
+                       64 1700000200-900-uninstall.tar.gz.tmp\n\
+                       64 1700000200-900-uninstall.tar.gz\n\
+                       32 1700000200-015-configure.tar.gz\n";
+        let snapshots = parse_listing(listing);
+        let ids: Vec<&str> = snapshots.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            ["1700000300-250-doctor-repair", "1700000200-900-uninstall", "1700000200-015-configure", "1700000100-configure"]
+        );
+        assert_eq!(snapshots[0].reason, "doctor-repair");
+        assert_eq!(snapshots[0].created_at, 1700000300);
+        assert_eq!(snapshots[0].size, 80);
+        assert_eq!(snapshots[3].reason, "configure");
+    }
+
+    #[test]
+    fn test_snapshot_take_diff_restore_and_prune() {
+        let home_dir = TempDir::new("snapshots");
+        let home = home_dir.as_str();
+        let root = home_dir.join(".openclaw");
+        fs::create_dir_all(root.join("workspace")).unwrap();
+        fs::create_dir_all(root.join("agents/main/sessions")).unwrap();
+        fs::write(root.join("openclaw.json"), r#"{"gateway":{"auth":{"token":"gateway-secret"}}}"#).unwrap();
+        fs::write(root.join("workspace/SOUL.md"), "be kind\n").unwrap();
+        fs::write(root.join("agents/main/sessions/log.jsonl"), "{}\n").unwrap();
+
+        // Nothing to snapshot before openclaw is set up
+        let empty = TempDir::new("no_openclaw");
+        assert_eq!(take(&ShellTarget, empty.as_str(), "configure").unwrap(), None);
+
+        // Older snapshots past the retention limit are pruned when a new one is taken
+        let snapshot_dir = home_dir.join(".openclaw-snapshots");
+        fs::create_dir_all(&snapshot_dir).unwrap();
+        for i in 0..KEEP_SNAPSHOTS {
+            fs::write(snapshot_dir.join(format!("{}-configure.tar.gz", 1000 + i)), "").unwrap();
+        }
+        let taken = take(&ShellTarget, home, "uninstall").unwrap().unwrap();
+        let snapshots = list(&ShellTarget, home).unwrap();
+        assert_eq!(snapshots.len(), KEEP_SNAPSHOTS);
+        assert_eq!(snapshots[0], taken);
+        assert!(!snapshot_dir.join("1000-configure.tar.gz").exists());
+        assert!(snapshot_dir.join("1001-configure.tar.gz").exists());
+
+        fs::write(root.join("openclaw.json"), r#"{"gateway":{"auth":{"token":"new-secret"}},"hooks":{}}"#).unwrap();
+        fs::remove_file(root.join("workspace/SOUL.md")).unwrap();
+        fs::write(root.join("workspace/USER.md"), "Sam\n").unwrap();
+
+        let changes = diff(&ShellTarget, home, &taken.id).unwrap();
+        let status = |name: &str| changes.iter().find(|f| f.path.ends_with(name)).map(|f| f.status.as_str());
+        assert_eq!(status("openclaw.json"), Some("modified"));
+        assert_eq!(status("SOUL.md"), Some("added"));
+        assert_eq!(status("USER.md"), Some("removed"));
+        assert_eq!(status("log.jsonl"), None);
+        assert!(changes.iter().all(|f| !f.diff.contains("secret")));
+        assert!(changes.iter().find(|f| f.path.ends_with("USER.md")).unwrap().diff.contains("+++ /dev/null"));
+
+        restore(&ShellTarget, home, &taken.id).unwrap();
+        assert_eq!(fs::read_to_string(root.join("workspace/SOUL.md")).unwrap(), "be kind\n");
+        assert!(!root.join("workspace/USER.md").exists());
+        assert!(fs::read_to_string(root.join("openclaw.json")).unwrap().contains("gateway-secret"));
+        // Session logs are not snapshotted, so a restore leaves them alone
+        assert!(root.join("agents/main/sessions/log.jsonl").exists());
+        // The state before the restore was itself snapshotted, and the restored one kept
+        let snapshots = list(&ShellTarget, home).unwrap();
+        assert!(snapshots.iter().any(|s| s.reason == "restore"));
+        assert!(snapshots.contains(&taken));
+        assert!(restore(&ShellTarget, home, "1-missing").is_err());
+
+        // Snapshots taken within the same second do not overwrite each other
+        let first = take(&ShellTarget, home, "configure").unwrap().unwrap();
+        let second = take(&ShellTarget, home, "configure").unwrap().unwrap();
+        assert_ne!(first.id, second.id);
+        let snapshots = list(&ShellTarget, home).unwrap();
+        assert!(snapshots.contains(&first) && snapshots.contains(&second));
+    }
+}
@@ -0,0 +1,427 @@
+// OpenSSH client config (~/.ssh/config) support.
+//
+// Only the options that change where and how we connect are understood:
+// Host, HostName, User, Port, IdentityFile and ProxyJump. Everything else
+// (including Match blocks and Include) is skipped. As in OpenSSH, the first
+// value obtained for an option wins, so specific Host blocks must come
+// before wildcard ones.
+
+use crate::{parse_ssh_host, RemoteInfo};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Guards against ProxyJump cycles (a -> b -> a) in the config.
+const MAX_JUMP_DEPTH: usize = 8;
+
+struct HostBlock {
+    patterns: Vec<String>,
+    options: Vec<(String, String)>,
+}
+
+pub(crate) struct SshConfig {
+    blocks: Vec<HostBlock>,
+}
+
+/// Options that apply to one host after all matching blocks are merged.
+#[derive(Default, Debug, PartialEq)]
+pub(crate) struct HostOptions {
+    pub(crate) hostname: Option<String>,
+    pub(crate) user: Option<String>,
+    pub(crate) port: Option<u16>,
+    pub(crate) identity_file: Option<String>,
+    pub(crate) proxy_jump: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SshConfigHost {
+    alias: String,
+    hostname: Option<String>,
+    user: Option<String>,
+    port: Option<u16>,
+    identity_file: Option<String>,
+    proxy_jump: Option<String>,
+}
+
+fn config_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|h| h.join(".ssh").join("config"))
+}
+
+/// Split a config line into keyword and value. Accepts both "Key value" and "Key=value".
+fn split_option(line: &str) -> Option<(String, String)> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let key_end = line.find(|c: char| c.is_whitespace() || c == '=')?;
+    let key = line[..key_end].to_lowercase();
+    let rest = line[key_end..].trim_start();
+    let rest = rest.strip_prefix('=').unwrap_or(rest).trim();
+    if rest.is_empty() {
+        return None;
+    }
+    Some((key, rest.to_string()))
+}
+
+/// Split a value into words, honouring double quotes (used for paths with spaces).
+fn split_words(value: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    for c in value.chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            c if c.is_whitespace() && !in_quotes => {
+                if !current.is_empty() {
+                    words.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+/// Glob match supporting `*` and `?`, as used by Host patterns.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let t: Vec<char> = text.chars().collect();
+    let (mut pi, mut ti) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+
+    while ti < t.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == t[ti]) {
+            pi += 1;
+            ti += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some((pi, ti));
+            pi += 1;
+        } else if let Some((sp, st)) = star {
+            pi = sp + 1;
+            ti = st + 1;
+            star = Some((sp, st + 1));
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|&c| c == '*')
+}
+
+impl HostBlock {
+    /// A block applies when any positive pattern matches and no negated one does.
+    fn matches(&self, host: &str) -> bool {
+        let host = host.to_lowercase();
+        let mut matched = false;
+        for pattern in &self.patterns {
+            let pattern = pattern.to_lowercase();
+            if let Some(negated) = pattern.strip_prefix('!') {
+                if glob_match(negated, &host) {
+                    return false;
+                }
+            } else if glob_match(&pattern, &host) {
+                matched = true;
+            }
+        }
+        matched
+    }
+}
+
+impl SshConfig {
+    pub(crate) fn parse(text: &str) -> SshConfig {
+        // Options before the first Host line apply to every host
+        let mut blocks = vec![HostBlock { patterns: vec!["*".to_string()], options: Vec::new() }];
+        // Options inside Match blocks are not evaluated
+        let mut skipping = false;
+
+        for line in text.lines() {
+            let Some((key, value)) = split_option(line) else { continue };
+            match key.as_str() {
+                "host" => {
+                    skipping = false;
+                    blocks.push(HostBlock { patterns: split_words(&value), options: Vec::new() });
+                }
+                "match" => skipping = true,
+                _ if skipping => {}
+                _ => {
+                    if let Some(block) = blocks.last_mut() {
+                        block.options.push((key, value));
+                    }
+                }
+            }
+        }
+
+        SshConfig { blocks }
+    }
+
+    /// Read ~/.ssh/config. A missing or unreadable file behaves like an empty config.
+    pub(crate) fn load() -> SshConfig {
+        let text = config_path()
+            .and_then(|p| fs::read_to_string(p).ok())
+            .unwrap_or_default();
+        SshConfig::parse(&text)
+    }
+
+    /// Merge every block matching `host`, keeping the first value seen for each option.
+    pub(crate) fn lookup(&self, host: &str) -> HostOptions {
+        let mut opts = HostOptions::default();
+        for block in self.blocks.iter().filter(|b| b.matches(host)) {
+            for (key, value) in &block.options {
+                let first_word = || split_words(value).into_iter().next();
+                match key.as_str() {
+                    "hostname" if opts.hostname.is_none() => opts.hostname = first_word(),
+                    "user" if opts.user.is_none() => opts.user = first_word(),
+                    "port" if opts.port.is_none() => opts.port = value.trim().parse().ok(),
+                    "identityfile" if opts.identity_file.is_none() => opts.identity_file = first_word(),
+                    "proxyjump" if opts.proxy_jump.is_none() => opts.proxy_jump = first_word(),
+                    _ => {}
+                }
+            }
+        }
+        opts
+    }
+
+    /// Host aliases that name a single host (no wildcards or negation), in file order.
+    pub(crate) fn aliases(&self) -> Vec<String> {
+        let mut aliases: Vec<String> = Vec::new();
+        for block in &self.blocks {
+            for pattern in &block.patterns {
+                if pattern.contains(['*', '?', '!']) || aliases.contains(pattern) {
+                    continue;
+                }
+                aliases.push(pattern.clone());
+            }
+        }
+        aliases
+    }
+
+    /// Fill in whatever the user left unset on `remote` from the config entry for its host.
+    /// Explicit values from the form always win over the config file.
+    pub(crate) fn resolve(&self, remote: &RemoteInfo) -> RemoteInfo {
+        self.resolve_with_depth(remote, 0)
+    }
+
+    fn resolve_with_depth(&self, remote: &RemoteInfo, depth: usize) -> RemoteInfo {
+        let mut resolved = remote.clone();
+        let Ok((alias, embedded_port)) = parse_ssh_host(&remote.ip) else {
+            // Leave invalid input alone so the connect path reports it
+            return resolved;
+        };
+        let opts = self.lookup(&alias);
+        let original_port = remote.port.or(embedded_port);
+
+        if let Some(hostname) = opts.hostname {
+            resolved.ip = hostname.replace("%h", &alias);
+            resolved.port = original_port;
+        }
+        if resolved.user.trim().is_empty() {
+            if let Some(user) = opts.user {
+                resolved.user = user;
+            }
+        }
+        if original_port.is_none() {
+            resolved.port = opts.port;
+        }
+
+        let has_password = remote.password.as_deref().is_some_and(|p| !p.is_empty());
+        if remote.private_key_path.is_none() && !has_password {
+            // Like ssh, silently ignore identity files that do not exist
+            resolved.private_key_path = opts
+                .identity_file
+                .map(|f| expand_path(&f, &alias, &resolved.user))
+                .filter(|f| Path::new(f).exists());
+        }
+
+        let explicit_hops = remote.jump_hosts.as_ref().is_some_and(|h| !h.is_empty());
+        let hops = if explicit_hops || depth >= MAX_JUMP_DEPTH {
+            remote.jump_hosts.clone().unwrap_or_default()
+        } else {
+            opts.proxy_jump
+                .filter(|j| !j.eq_ignore_ascii_case("none"))
+                .map(|j| parse_proxy_jump(&j))
+                .unwrap_or_default()
+        };
+
+        // Each hop may itself be an alias with its own ProxyJump; flatten into one chain
+        let mut chain = Vec::new();
+        if depth < MAX_JUMP_DEPTH {
+            for hop in &hops {
+                let mut hop = self.resolve_with_depth(hop, depth + 1);
+                chain.extend(hop.jump_hosts.take().unwrap_or_default());
+                chain.push(hop);
+            }
+        }
+        resolved.jump_hosts = if chain.is_empty() { None } else { Some(chain) };
+        resolved
+    }
+}
+
+/// Expand `~` and the common tokens (%d home, %h host, %r remote user, %u local user, %%).
+fn expand_path(path: &str, host: &str, remote_user: &str) -> String {
+    let home = dirs::home_dir().map(|h| h.to_string_lossy().to_string()).unwrap_or_default();
+    let local_user = std::env::var("USER").or_else(|_| std::env::var("USERNAME")).unwrap_or_default();
+
+    let mut out = String::new();
+    let mut chars = path.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('d') => out.push_str(&home),
+            Some('h') => out.push_str(host),
+            Some('r') => out.push_str(remote_user),
+            Some('u') => out.push_str(&local_user),
+            Some('%') => out.push('%'),
+            Some(other) => {
+                out.push('%');
+                out.push(other);
+            }
+            None => out.push('%'),
+        }
+    }
+
+    if out == "~" {
+        home
+    } else if let Some(rest) = out.strip_prefix("~/") {
+        format!("{}/{}", home, rest)
+    } else {
+        out
+    }
+}
+
+/// Parse a ProxyJump value: comma-separated `[user@]host[:port]` or `ssh://[user@]host[:port]`.
+pub(crate) fn parse_proxy_jump(value: &str) -> Vec<RemoteInfo> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|spec| {
+            let spec = spec.strip_prefix("ssh://").unwrap_or(spec);
+            let (user, host) = match spec.rsplit_once('@') {
+                Some((user, host)) => (user.to_string(), host),
+                None => (String::new(), spec),
+            };
+            RemoteInfo {
+                ip: host.to_string(),
+                user,
+                port: None,
+                password: None,
+                private_key_path: None,
+                key_passphrase: None,
+                jump_hosts: None,
+                sudo_password: None,
+            }
+        })
+        .collect()
+}
+
+/// Resolve `remote` against ~/.ssh/config.
+pub(crate) fn resolve_remote(remote: &RemoteInfo) -> RemoteInfo {
+    SshConfig::load().resolve(remote)
+}
+
+/// List the concrete host aliases from ~/.ssh/config with the options each resolves to.
+pub(crate) fn list_hosts() -> Vec<SshConfigHost> {
+    let config = SshConfig::load();
+    config
+        .aliases()
+        .into_iter()
+        .map(|alias| {
+            let opts = config.lookup(&alias);
+            SshConfigHost {
+                hostname: opts.hostname.map(|h| h.replace("%h", &alias)),
+                identity_file: opts.identity_file.map(|f| expand_path(&f, &alias, opts.user.as_deref().unwrap_or(""))),
+                user: opts.user,
+                port: opts.port,
+                proxy_jump: opts.proxy_jump,
+                alias,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ssh_config_lookup_first_value_wins() {
+        let config = SshConfig::parse(
+            "# global defaults\n\
+             ServerAliveInterval 30\n\
+             Host prod web-*\n\
+             \tHostName 10.1.2.3\n\
+             \tUser deploy\n\
+             \tPort=2222\n\
+             \tIdentityFile \"/keys/prod key\"\n\
+             Match exec \"true\"\n\
+             \tUser ignored\n\
+             Host * !bastion\n\
+             \tUser fallback\n\
+             \tPort 22\n\
+             \tProxyJump bastion\n",
+        );
+
+        let prod = config.lookup("PROD");
+        assert_eq!(prod.hostname.as_deref(), Some("10.1.2.3"));
+        assert_eq!(prod.user.as_deref(), Some("deploy"));
+        assert_eq!(prod.port, Some(2222));
+        assert_eq!(prod.identity_file.as_deref(), Some("/keys/prod key"));
+        assert_eq!(prod.proxy_jump.as_deref(), Some("bastion"));
+
+        assert_eq!(config.lookup("web-01").user.as_deref(), Some("deploy"));
+        let bastion = config.lookup("bastion");
+        assert_eq!(bastion.user, None);
+        assert_eq!(bastion.proxy_jump, None);
+
+        assert_eq!(config.aliases(), vec!["prod".to_string()]);
+    }
+
+    #[test]
+    fn test_ssh_config_resolves_remote_and_proxy_jump() {
+        let config = SshConfig::parse(
+            "Host app\n  HostName app.internal\n  User ubuntu\n  ProxyJump ops@gw:2200,edge\n\
+             Host edge\n  HostName edge.example.com\n  ProxyJump outer\n\
+             Host loop\n  ProxyJump loop\n",
+        );
+
+        let remote: RemoteInfo = serde_json::from_str(r#"{ "ip": "app", "user": "" }"#).unwrap();
+        let resolved = config.resolve(&remote);
+        assert_eq!(resolved.ip, "app.internal");
+        assert_eq!(resolved.user, "ubuntu");
+        assert_eq!(resolved.port, None);
+
+        let hops: Vec<(String, String)> = resolved
+            .jump_hosts
+            .unwrap()
+            .into_iter()
+            .map(|h| (h.ip, h.user))
+            .collect();
+        assert_eq!(
+            hops,
+            vec![
+                ("gw:2200".to_string(), "ops".to_string()),
+                ("outer".to_string(), String::new()),
+                ("edge.example.com".to_string(), String::new()),
+            ]
+        );
+
+        // Explicit form values win over the config file
+        let remote: RemoteInfo = serde_json::from_str(
+            r#"{ "ip": "app:2022", "user": "root", "jumpHosts": [{ "ip": "direct", "user": "" }] }"#
+        ).unwrap();
+        let resolved = config.resolve(&remote);
+        assert_eq!(resolved.ip, "app.internal");
+        assert_eq!(resolved.port, Some(2022));
+        assert_eq!(resolved.user, "root");
+        assert_eq!(resolved.jump_hosts.unwrap().len(), 1);
+
+        // A ProxyJump cycle terminates instead of recursing forever
+        let remote: RemoteInfo = serde_json::from_str(r#"{ "ip": "loop", "user": "me" }"#).unwrap();
+        assert!(!config.resolve(&remote).jump_hosts.unwrap().is_empty());
+    }
+}
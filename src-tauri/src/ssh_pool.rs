@@ -0,0 +1,194 @@
+// Shared SSH sessions, one per (host, user, credentials), held in Tauri state.
+//
+// Commands check a session out with `get`, which reuses the pooled one after
+// a cheap liveness probe and reconnects transparently when the probe fails.
+// A background thread sends keepalives and drops sessions that sat idle too
+// long. Tunnels run their session in non-blocking mode, so they take a
+// dedicated session instead of sharing one with command execution.
+
+use crate::{connect_ssh, ssh_config, RemoteInfo, SshError, SESSION_READ_TIMEOUT_MS};
+use ssh2::Session;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex, Weak};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// How often libssh2 sends keepalive messages on an otherwise quiet session.
+const KEEPALIVE_INTERVAL_SECS: u32 = 30;
+// Sessions used more recently than this are handed out without a probe.
+const LIVENESS_GRACE: Duration = Duration::from_secs(5);
+// Command sessions not checked out for longer than this are released. Callers
+// still holding a clone keep it open until they finish.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+const SWEEP_INTERVAL: Duration = Duration::from_secs(15);
+
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub(crate) struct PoolKey {
+    endpoint: String,
+    user: String,
+    // Hash of how the session authenticates (password, key and passphrase, jump hosts),
+    // so a session opened with one set of credentials is not handed to another
+    credentials: u64,
+}
+
+impl PoolKey {
+    pub(crate) fn new(remote: &RemoteInfo) -> PoolKey {
+        let mut hasher = DefaultHasher::new();
+        hash_credentials(remote, &mut hasher);
+        PoolKey { endpoint: remote.endpoint(), user: remote.user.clone(), credentials: hasher.finish() }
+    }
+}
+
+fn hash_credentials(remote: &RemoteInfo, hasher: &mut DefaultHasher) {
+    remote.password.hash(hasher);
+    remote.private_key_path.hash(hasher);
+    remote.key_passphrase.hash(hasher);
+    for hop in remote.jump_hosts.iter().flatten() {
+        hop.endpoint().hash(hasher);
+        hop.user.hash(hasher);
+        hash_credentials(hop, hasher);
+    }
+}
+
+struct PooledSession {
+    sess: Session,
+    last_used: Instant,
+}
+
+// Each key has its own slot so a slow connect to one host does not block the others,
+// while concurrent callers for the same host wait for one connect instead of racing.
+type Slot = Arc<Mutex<Option<PooledSession>>>;
+
+#[derive(Clone)]
+pub(crate) struct SshPool {
+    slots: Arc<Mutex<HashMap<PoolKey, Slot>>>,
+}
+
+impl SshPool {
+    pub(crate) fn new() -> SshPool {
+        let pool = SshPool { slots: Arc::new(Mutex::new(HashMap::new())) };
+        let weak = Arc::downgrade(&pool.slots);
+        thread::spawn(move || keepalive_loop(weak));
+        pool
+    }
+
+    fn slot(&self, key: &PoolKey) -> Slot {
+        let mut slots = self.slots.lock().unwrap_or_else(|e| e.into_inner());
+        slots.entry(key.clone()).or_default().clone()
+    }
+
+    /// A blocking session for running commands on `remote`.
+    pub(crate) fn get(&self, remote: &RemoteInfo) -> Result<Session, SshError> {
+        let remote = ssh_config::resolve_remote(remote);
+        let slot = self.slot(&PoolKey::new(&remote));
+        let mut entry = slot.lock().unwrap_or_else(|e| e.into_inner());
+
+        if let Some(pooled) = entry.as_mut() {
+            if pooled.last_used.elapsed() < LIVENESS_GRACE || is_alive(&pooled.sess) {
+                pooled.last_used = Instant::now();
+                return Ok(pooled.sess.clone());
+            }
+            *entry = None;
+        }
+
+        let sess = connect_ssh(&remote)?;
+        sess.set_keepalive(true, KEEPALIVE_INTERVAL_SECS);
+        sess.set_timeout(SESSION_READ_TIMEOUT_MS);
+        *entry = Some(PooledSession { sess: sess.clone(), last_used: Instant::now() });
+        Ok(sess)
+    }
+
+    /// A session of its own, outside the pool, for long-running work such as installs.
+    /// Long blocking reads would otherwise hold up every command sharing the pooled
+    /// session, and streaming output switches the session to non-blocking mode.
+    pub(crate) fn get_dedicated(&self, remote: &RemoteInfo) -> Result<Session, SshError> {
+        let sess = connect_ssh(&ssh_config::resolve_remote(remote))?;
+        sess.set_keepalive(true, KEEPALIVE_INTERVAL_SECS);
+        sess.set_timeout(SESSION_READ_TIMEOUT_MS);
+        Ok(sess)
+    }
+
+}
+
+/// Round-trip check: opening a channel needs a reply from the server, so it
+/// fails fast on a dead connection where a plain write would not.
+fn is_alive(sess: &Session) -> bool {
+    match sess.channel_session() {
+        Ok(mut channel) => {
+            let _ = channel.close();
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+fn keepalive_loop(slots: Weak<Mutex<HashMap<PoolKey, Slot>>>) {
+    loop {
+        thread::sleep(SWEEP_INTERVAL);
+        let Some(slots) = slots.upgrade() else { return };
+
+        let entries: Vec<(PoolKey, Slot)> = {
+            let slots = slots.lock().unwrap_or_else(|e| e.into_inner());
+            slots.iter().map(|(k, v)| (k.clone(), v.clone())).collect()
+        };
+
+        let mut dead = Vec::new();
+        for (key, slot) in entries {
+            // Skip slots a caller is connecting right now
+            let Ok(mut entry) = slot.try_lock() else { continue };
+            let Some(pooled) = entry.as_ref() else {
+                dead.push(key);
+                continue;
+            };
+            if pooled.last_used.elapsed() > IDLE_TIMEOUT || pooled.sess.keepalive_send().is_err() {
+                *entry = None;
+                dead.push(key);
+            }
+        }
+
+        if !dead.is_empty() {
+            let mut slots = slots.lock().unwrap_or_else(|e| e.into_inner());
+            for key in dead {
+                if let Some(slot) = slots.get(&key) {
+                    if slot.try_lock().map(|e| e.is_none()).unwrap_or(false) {
+                        slots.remove(&key);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ssh_pool_key_by_host_user_and_credentials() {
+        let a: RemoteInfo = serde_json::from_str(r#"{ "ip": "10.0.0.5", "user": "ubuntu", "password": "one" }"#).unwrap();
+        let b: RemoteInfo = serde_json::from_str(r#"{ "ip": "10.0.0.5:22", "user": "ubuntu", "password": "one" }"#).unwrap();
+        let c: RemoteInfo = serde_json::from_str(r#"{ "ip": "10.0.0.5", "user": "root" }"#).unwrap();
+        let d: RemoteInfo = serde_json::from_str(r#"{ "ip": "10.0.0.5", "user": "ubuntu", "port": 2222 }"#).unwrap();
+
+        assert_eq!(PoolKey::new(&a), PoolKey::new(&b));
+        assert_ne!(PoolKey::new(&a), PoolKey::new(&c));
+        assert_ne!(PoolKey::new(&a), PoolKey::new(&d));
+
+        // A different password, key or passphrase must not reuse the session
+        let other_password: RemoteInfo = serde_json::from_str(r#"{ "ip": "10.0.0.5", "user": "ubuntu", "password": "two" }"#).unwrap();
+        assert_ne!(PoolKey::new(&a), PoolKey::new(&other_password));
+        let key: RemoteInfo =
+            serde_json::from_str(r#"{ "ip": "10.0.0.5", "user": "ubuntu", "privateKeyPath": "/keys/id_ed25519" }"#).unwrap();
+        let other_key: RemoteInfo =
+            serde_json::from_str(r#"{ "ip": "10.0.0.5", "user": "ubuntu", "privateKeyPath": "/keys/id_rsa" }"#).unwrap();
+        let with_passphrase: RemoteInfo = serde_json::from_str(
+            r#"{ "ip": "10.0.0.5", "user": "ubuntu", "privateKeyPath": "/keys/id_ed25519", "keyPassphrase": "secret" }"#,
+        )
+        .unwrap();
+        assert_ne!(PoolKey::new(&key), PoolKey::new(&other_key));
+        assert_ne!(PoolKey::new(&key), PoolKey::new(&with_passphrase));
+        assert_ne!(PoolKey::new(&a), PoolKey::new(&key));
+    }
+}
@@ -0,0 +1,111 @@
+// Helpers shared by the unit tests in main.rs and in the modules.
+
+use crate::config_plan::ConfigTarget;
+use crate::AgentConfig;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// A fresh directory under the system temp dir, removed with its contents when dropped,
+/// so a failing test does not leave it behind.
+pub(crate) struct TempDir(PathBuf);
+
+impl TempDir {
+    pub(crate) fn new(name: &str) -> TempDir {
+        let path = std::env::temp_dir().join(format!("clawnetes_{}_{}", name, rand::random::<u32>()));
+        fs::create_dir_all(&path).unwrap_or_else(|e| panic!("Failed to create {}: {}", path.display(), e));
+        TempDir(path)
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.0
+    }
+
+    pub(crate) fn join(&self, path: impl AsRef<Path>) -> PathBuf {
+        self.0.join(path)
+    }
+
+    /// The path as a string, the way targets take home directories.
+    pub(crate) fn as_str(&self) -> &str {
+        self.0.to_str().expect("temp dir path is not UTF-8")
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Wizard answers touching every part of openclaw.json, with secrets to check masking against.
+pub(crate) fn wizard_config() -> AgentConfig {
+    serde_json::from_value(serde_json::json!({
+        "provider": "openai",
+        "api_key": "sk-secret",
+        "auth_method": "codex",
+        "base_url": "https://llm.internal/v1",
+        "model": "openai/gpt-5",
+        "user_name": "Sam",
+        "agent_name": "Claw",
+        "telegram_token": "123:abc",
+        "gateway_port": 28789,
+        "sandbox_mode": "partial",
+        "tools_mode": "denylist",
+        "denied_tools": ["exec"],
+        "fallback_models": ["anthropic/claude-sonnet-4-5"],
+        "heartbeat_mode": "idle",
+        "memory_enabled": true,
+        "cron_jobs": [{ "name": "digest", "schedule": "0 9 * * *", "command": "summarize" }],
+        "agents": [{
+            "id": "research",
+            "name": "Researcher",
+            "model": "anthropic/claude-opus-4-6",
+            "subagents": { "allowAgents": ["main"] }
+        }]
+    }))
+    .unwrap()
+}
+
+// Files in memory, standing in for a machine in plan tests
+#[derive(Default)]
+pub(crate) struct MemoryTarget {
+    pub(crate) files: BTreeMap<String, String>,
+}
+
+impl ConfigTarget for MemoryTarget {
+    fn read(&self, path: &str) -> Result<String, String> {
+        Ok(self.files.get(path).cloned().unwrap_or_default())
+    }
+    fn write(&self, _path: &str, _contents: &str, _mode: i32) -> Result<(), String> {
+        Err("the plan must not write to the target".to_string())
+    }
+    fn mkdir_p(&self, _path: &str) -> Result<(), String> {
+        Err("the plan must not create directories".to_string())
+    }
+    fn run(&self, cmd: &str) -> Result<String, String> {
+        Err(format!("the plan must not run {}", cmd))
+    }
+}
+
+// Runs commands with the system shell, in place of a machine over SSH
+pub(crate) struct ShellTarget;
+
+impl ConfigTarget for ShellTarget {
+    fn read(&self, path: &str) -> Result<String, String> {
+        Ok(fs::read_to_string(path).unwrap_or_default())
+    }
+    fn write(&self, path: &str, contents: &str, _mode: i32) -> Result<(), String> {
+        fs::write(path, contents).map_err(|e| e.to_string())
+    }
+    fn mkdir_p(&self, path: &str) -> Result<(), String> {
+        fs::create_dir_all(path).map_err(|e| e.to_string())
+    }
+    fn run(&self, cmd: &str) -> Result<String, String> {
+        let output = Command::new("sh").arg("-c").arg(cmd).output().map_err(|e| e.to_string())?;
+        if !output.status.success() {
+            return Err(String::from_utf8_lossy(&output.stderr).to_string());
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+}
@@ -0,0 +1,840 @@
+// SSH tunnels between a local port and a remote host, tracked in Tauri state.
+//
+// Local tunnels forward a local port to the remote gateway so its dashboard can
+// be opened here; reverse tunnels publish the local gateway on a port of the
+// remote host. Each tunnel gets an id and one authenticated SSH session of its
+// own, so several can run at the same time. Every forwarded connection becomes
+// a channel on that session, and a single thread per tunnel moves data for all
+// of them, waking only when the listener, the SSH socket or a socket is ready.
+//
+// The same thread supervises the session: keepalive replies show the server is
+// still there, and a session that goes quiet or fails is replaced, backing off
+// between attempts. Status changes go to the listener set with `on_status`.
+
+use crate::ssh_pool::SshPool;
+use crate::RemoteInfo;
+use polling::{Event, Events, PollMode, Poller};
+use rand::Rng;
+use ssh2::{Channel, ErrorCode, Session};
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+// Poller keys for the listener and the SSH socket; browser connections count up from FIRST_CONNECTION.
+const LISTENER: usize = 0;
+const SESSION: usize = 1;
+const FIRST_CONNECTION: usize = 2;
+// Reading from one side pauses while this much is still waiting to be written to the other.
+const BUFFER_LIMIT: usize = 256 * 1024;
+const READ_CHUNK: usize = 16384;
+// libssh2 error codes: the call would block, and the server refused to open a channel
+const LIBSSH2_ERROR_EAGAIN: i32 = -37;
+const LIBSSH2_ERROR_CHANNEL_FAILURE: i32 = -21;
+// Keepalive interval on tunnel sessions. Any traffic from the server, keepalive replies
+// included, shows it is alive; after DEGRADED_AFTER silent intervals the tunnel is
+// reported degraded and after DEAD_AFTER the session is replaced.
+const KEEPALIVE_SECS: u32 = 15;
+const DEGRADED_AFTER: u32 = 2;
+const DEAD_AFTER: u32 = 4;
+// Reconnect delays double from BACKOFF_INITIAL up to BACKOFF_MAX.
+const BACKOFF_INITIAL: Duration = Duration::from_secs(1);
+const BACKOFF_MAX: Duration = Duration::from_secs(60);
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum TunnelMode {
+    // A local port forwarded to a port on the remote host
+    Local,
+    // A port on the remote host forwarded back to a local port
+    Reverse,
+}
+
+#[derive(serde::Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum TunnelStatus {
+    // Opening the SSH session
+    Connecting,
+    // Forwarding, and the server answers keepalives
+    Up,
+    // Forwarding, but the server has not been heard from for a while
+    Degraded,
+    // The session was lost; waiting to reconnect
+    Down,
+    // Stopped by the user
+    Stopped,
+}
+
+#[derive(serde::Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TunnelInfo {
+    pub(crate) id: String,
+    // Host as entered (IP, hostname or ssh config alias) and SSH user
+    pub(crate) host: String,
+    pub(crate) user: String,
+    pub(crate) mode: TunnelMode,
+    pub(crate) local_port: u16,
+    pub(crate) remote_port: u16,
+    // Seconds since the Unix epoch
+    pub(crate) started_at: u64,
+    pub(crate) status: TunnelStatus,
+    // Why the session was last lost; cleared once it is up again
+    pub(crate) last_error: Option<String>,
+    // Bytes carried from the gateway to the browser and back, across reconnects
+    pub(crate) bytes_in: u64,
+    pub(crate) bytes_out: u64,
+    pub(crate) connections_total: u64,
+    pub(crate) connections_active: u64,
+    pub(crate) reconnects: u64,
+}
+
+/// Called with a snapshot of the tunnel whenever its status changes.
+pub(crate) type StatusListener = Arc<dyn Fn(&TunnelInfo) + Send + Sync>;
+
+#[derive(Default)]
+struct TunnelStats {
+    bytes_in: AtomicU64,
+    bytes_out: AtomicU64,
+    connections_total: AtomicU64,
+    connections_active: AtomicU64,
+    reconnects: AtomicU64,
+}
+
+pub(crate) struct Tunnel {
+    info: Mutex<TunnelInfo>,
+    stats: TunnelStats,
+    remote: RemoteInfo,
+    stop: AtomicBool,
+    // The tunnel thread waits on this; `stop` wakes it through `notify`
+    poller: Poller,
+    on_status: Option<StatusListener>,
+}
+
+impl Tunnel {
+    fn info(&self) -> TunnelInfo {
+        let mut info = self.info.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        info.bytes_in = self.stats.bytes_in.load(Ordering::Relaxed);
+        info.bytes_out = self.stats.bytes_out.load(Ordering::Relaxed);
+        info.connections_total = self.stats.connections_total.load(Ordering::Relaxed);
+        info.connections_active = self.stats.connections_active.load(Ordering::Relaxed);
+        info.reconnects = self.stats.reconnects.load(Ordering::Relaxed);
+        info
+    }
+
+    /// Record a status change (with the error that caused it, if any) and tell the listener.
+    pub(crate) fn set_status(&self, status: TunnelStatus, error: Option<String>) {
+        {
+            let mut info = self.info.lock().unwrap_or_else(|e| e.into_inner());
+            if info.status == status && error.is_none() {
+                return;
+            }
+            info.status = status;
+            if status == TunnelStatus::Up {
+                info.last_error = None;
+            } else if error.is_some() {
+                info.last_error = error;
+            }
+        }
+        if let Some(listener) = &self.on_status {
+            listener(&self.info());
+        }
+    }
+
+    pub(crate) fn stopped(&self) -> bool {
+        self.stop.load(Ordering::Relaxed)
+    }
+
+    fn request_stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+        let _ = self.poller.notify();
+    }
+
+    /// Sleep for `delay`, waking early when the tunnel is stopped.
+    fn pause(&self, delay: Duration) {
+        if !self.stopped() {
+            let _ = self.poller.wait(&mut Events::new(), Some(delay));
+        }
+    }
+
+    /// Whether this tunnel already uses `remote_port` on the host behind `remote` in `mode`.
+    fn serves(&self, remote: &RemoteInfo, mode: TunnelMode, remote_port: Option<u16>) -> bool {
+        let info = self.info();
+        self.remote.endpoint() == remote.endpoint()
+            && self.remote.user == remote.user
+            && info.mode == mode
+            && remote_port.is_none_or(|p| p == info.remote_port)
+    }
+
+    fn running(&self, remote: &RemoteInfo, mode: TunnelMode, remote_port: Option<u16>) -> bool {
+        !self.stopped() && self.serves(remote, mode, remote_port)
+    }
+}
+
+#[derive(Clone, Default)]
+pub(crate) struct TunnelRegistry {
+    tunnels: Arc<Mutex<HashMap<String, Arc<Tunnel>>>>,
+    on_status: Arc<Mutex<Option<StatusListener>>>,
+}
+
+fn new_tunnel_id() -> String {
+    let suffix: String = rand::thread_rng()
+        .sample_iter(&rand::distributions::Alphanumeric)
+        .take(8)
+        .map(char::from)
+        .collect();
+    format!("tunnel-{}", suffix.to_lowercase())
+}
+
+/// Bind the local end. An explicit port must be free; otherwise prefer the remote
+/// port number (so URLs look familiar) and fall back to any free port.
+pub(crate) fn bind_local(local_port: Option<u16>, remote_port: u16) -> Result<TcpListener, String> {
+    match local_port {
+        Some(port) => TcpListener::bind(("127.0.0.1", port)).map_err(|e| format!("Failed to bind local port {}: {}", port, e)),
+        None => TcpListener::bind(("127.0.0.1", remote_port))
+            .or_else(|_| TcpListener::bind(("127.0.0.1", 0)))
+            .map_err(|e| format!("Failed to bind a local port: {}", e)),
+    }
+}
+
+impl TunnelRegistry {
+    pub(crate) fn new() -> TunnelRegistry {
+        TunnelRegistry::default()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Arc<Tunnel>>> {
+        self.tunnels.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Report status changes of tunnels started from now on to `listener`.
+    pub(crate) fn on_status(&self, listener: impl Fn(&TunnelInfo) + Send + Sync + 'static) {
+        *self.on_status.lock().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(listener));
+    }
+
+    /// Start forwarding a local port to `remote_port` on the remote host. Starting a
+    /// tunnel that already exists for the same host and port returns the existing one.
+    pub(crate) fn start(&self, pool: &SshPool, remote: RemoteInfo, local_port: Option<u16>, remote_port: u16) -> Result<TunnelInfo, String> {
+        if let Some(existing) = self.lock().values().find(|t| t.running(&remote, TunnelMode::Local, Some(remote_port))) {
+            return Ok(existing.info());
+        }
+
+        let listener = bind_local(local_port, remote_port)?;
+        let local_port = listener.local_addr().map_err(|e| e.to_string())?.port();
+        // Connect up front so a bad password or unreachable host is reported to the caller
+        let link = Link::open(pool, &remote, TunnelMode::Local, remote_port)?;
+        let pool = pool.clone();
+        self.launch(remote, TunnelMode::Local, local_port, remote_port, move |tunnel| {
+            supervise(tunnel, Some(&listener), &pool, link)
+        })
+    }
+
+    /// Publish `local_port` on `remote_port` of the remote host. Whether the port is
+    /// reachable from other machines or only from the host itself is up to the server's
+    /// `GatewayPorts` setting. Port 0 lets the server pick one. Publishing a port that
+    /// is already published returns the existing tunnel.
+    pub(crate) fn start_reverse(&self, pool: &SshPool, remote: RemoteInfo, local_port: u16, remote_port: u16) -> Result<TunnelInfo, String> {
+        if remote_port != 0 {
+            if let Some(existing) = self.lock().values().find(|t| t.running(&remote, TunnelMode::Reverse, Some(remote_port))) {
+                return Ok(existing.info());
+            }
+        }
+
+        // Listening up front reports a port that is already taken on the server to the caller
+        let link = Link::open(pool, &remote, TunnelMode::Reverse, remote_port)?;
+        let remote_port = link.bound_port.unwrap_or(remote_port);
+        let pool = pool.clone();
+        self.launch(remote, TunnelMode::Reverse, local_port, remote_port, move |tunnel| supervise(tunnel, None, &pool, link))
+    }
+
+    /// Register a tunnel and run `serve` on its own thread. The tunnel leaves the
+    /// registry when `serve` returns.
+    pub(crate) fn launch<F>(&self, remote: RemoteInfo, mode: TunnelMode, local_port: u16, remote_port: u16, serve: F) -> Result<TunnelInfo, String>
+    where
+        F: FnOnce(&Tunnel) + Send + 'static,
+    {
+        let poller = Poller::new().map_err(|e| format!("Failed to create tunnel poller: {}", e))?;
+
+        let info = TunnelInfo {
+            id: new_tunnel_id(),
+            host: remote.ip.clone(),
+            user: remote.user.clone(),
+            mode,
+            local_port,
+            remote_port,
+            started_at: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+            status: TunnelStatus::Connecting,
+            last_error: None,
+            bytes_in: 0,
+            bytes_out: 0,
+            connections_total: 0,
+            connections_active: 0,
+            reconnects: 0,
+        };
+        let tunnel = Arc::new(Tunnel {
+            info: Mutex::new(info.clone()),
+            stats: TunnelStats::default(),
+            remote,
+            stop: AtomicBool::new(false),
+            poller,
+            on_status: self.on_status.lock().unwrap_or_else(|e| e.into_inner()).clone(),
+        });
+        self.lock().insert(info.id.clone(), tunnel.clone());
+
+        let registry = self.clone();
+        thread::spawn(move || {
+            serve(&tunnel);
+            tunnel.set_status(TunnelStatus::Stopped, None);
+            registry.lock().retain(|_, t| !Arc::ptr_eq(t, &tunnel));
+        });
+
+        Ok(info)
+    }
+
+    pub(crate) fn list(&self) -> Vec<TunnelInfo> {
+        let mut tunnels: Vec<TunnelInfo> = self.lock().values().map(|t| t.info()).collect();
+        tunnels.sort_by(|a, b| a.started_at.cmp(&b.started_at).then(a.id.cmp(&b.id)));
+        tunnels
+    }
+
+    pub(crate) fn stop(&self, id: &str) -> Result<(), String> {
+        let tunnel = self.lock().remove(id).ok_or_else(|| format!("No tunnel with id {}", id))?;
+        tunnel.request_stop();
+        Ok(())
+    }
+
+    pub(crate) fn stop_all(&self) {
+        for (_, tunnel) in self.lock().drain() {
+            tunnel.request_stop();
+        }
+    }
+
+    /// The running local tunnel to the host behind `remote`, if any.
+    pub(crate) fn find(&self, remote: &RemoteInfo) -> Option<TunnelInfo> {
+        self.lock().values().find(|t| t.running(remote, TunnelMode::Local, None)).map(|t| t.info())
+    }
+}
+
+/// One browser connection and the SSH channel that carries it.
+struct Connection {
+    stream: TcpStream,
+    // None while the channel open is still queued
+    channel: Option<Channel>,
+    // Bytes read from one side that the other side has not accepted yet
+    to_remote: Vec<u8>,
+    to_local: Vec<u8>,
+    local_eof: bool,
+    remote_eof: bool,
+    eof_sent: bool,
+    shutdown_sent: bool,
+    // Interest currently registered with the poller: (readable, writable)
+    interest: (bool, bool),
+}
+
+impl Connection {
+    fn new(stream: TcpStream) -> Connection {
+        Connection {
+            stream,
+            channel: None,
+            to_remote: Vec::new(),
+            to_local: Vec::new(),
+            local_eof: false,
+            remote_eof: false,
+            eof_sent: false,
+            shutdown_sent: false,
+            interest: (false, false),
+        }
+    }
+
+    /// Move whatever is ready in both directions without blocking. Returns true once
+    /// both sides have finished and the connection can be closed.
+    fn pump(&mut self, buf: &mut [u8], stats: &TunnelStats) -> io::Result<bool> {
+        let Some(channel) = self.channel.as_mut() else { return Ok(false) };
+
+        // Browser to gateway
+        if !self.local_eof && self.to_remote.len() < BUFFER_LIMIT {
+            match self.stream.read(buf) {
+                Ok(0) => self.local_eof = true,
+                Ok(n) => self.to_remote.extend_from_slice(&buf[..n]),
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
+                Err(e) => return Err(e),
+            }
+        }
+        while !self.to_remote.is_empty() {
+            match channel.write(&self.to_remote) {
+                Ok(n) => {
+                    self.to_remote.drain(..n);
+                    stats.bytes_out.fetch_add(n as u64, Ordering::Relaxed);
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err(e) => return Err(e),
+            }
+        }
+        if self.local_eof && self.to_remote.is_empty() && !self.eof_sent {
+            match channel.send_eof() {
+                Ok(()) => self.eof_sent = true,
+                Err(e) if would_block(&e) => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        // Gateway to browser
+        while !self.remote_eof && self.to_local.len() < BUFFER_LIMIT {
+            match channel.read(buf) {
+                Ok(0) => {
+                    self.remote_eof = channel.eof();
+                    break;
+                }
+                Ok(n) => self.to_local.extend_from_slice(&buf[..n]),
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err(e) => return Err(e),
+            }
+        }
+        while !self.to_local.is_empty() {
+            match self.stream.write(&self.to_local) {
+                Ok(n) => {
+                    self.to_local.drain(..n);
+                    stats.bytes_in.fetch_add(n as u64, Ordering::Relaxed);
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err(e) => return Err(e),
+            }
+        }
+        if self.remote_eof && self.to_local.is_empty() && !self.shutdown_sent {
+            let _ = self.stream.shutdown(Shutdown::Write);
+            self.shutdown_sent = true;
+        }
+
+        Ok(self.eof_sent && self.shutdown_sent)
+    }
+
+    /// What to wait for on the browser socket. Nothing is read until the channel is open.
+    fn wanted_interest(&self) -> (bool, bool) {
+        let readable = self.channel.is_some() && !self.local_eof && self.to_remote.len() < BUFFER_LIMIT;
+        (readable, !self.to_local.is_empty())
+    }
+}
+
+fn would_block(e: &ssh2::Error) -> bool {
+    e.code() == ErrorCode::Session(LIBSSH2_ERROR_EAGAIN)
+}
+
+/// The SSH socket as a poller source. The descriptor stays valid for as long as the session.
+#[cfg(unix)]
+fn session_source(sess: &Session) -> std::os::fd::BorrowedFd<'_> {
+    use std::os::fd::AsRawFd;
+    unsafe { std::os::fd::BorrowedFd::borrow_raw(sess.as_raw_fd()) }
+}
+
+#[cfg(windows)]
+fn session_source(sess: &Session) -> std::os::windows::io::BorrowedSocket<'_> {
+    use std::os::windows::io::AsRawSocket;
+    unsafe { std::os::windows::io::BorrowedSocket::borrow_raw(sess.as_raw_socket()) }
+}
+
+/// The delay before the reconnect attempt after one that waited `delay`.
+pub(crate) fn next_backoff(delay: Duration) -> Duration {
+    (delay * 2).min(BACKOFF_MAX)
+}
+
+/// An SSH session for one tunnel, with the server-side listener of a reverse tunnel.
+struct Link {
+    sess: Session,
+    remote_listener: Option<ssh2::Listener>,
+    // The port the server bound for a reverse tunnel
+    bound_port: Option<u16>,
+}
+
+impl Link {
+    fn open(pool: &SshPool, remote: &RemoteInfo, mode: TunnelMode, remote_port: u16) -> Result<Link, String> {
+        let sess = pool.get_dedicated(remote)?;
+        match mode {
+            TunnelMode::Local => Ok(Link { sess, remote_listener: None, bound_port: None }),
+            TunnelMode::Reverse => {
+                let (listener, port) = sess
+                    .channel_forward_listen(remote_port, None, None)
+                    .map_err(|e| format!("Failed to listen on port {} of the remote host: {}", remote_port, e))?;
+                Ok(Link { sess, remote_listener: Some(listener), bound_port: Some(port) })
+            }
+        }
+    }
+}
+
+/// Keep the tunnel forwarding until it is stopped. Whenever the session is lost,
+/// connect a new one, waiting longer after each failed attempt.
+fn supervise(tunnel: &Tunnel, listener: Option<&TcpListener>, pool: &SshPool, link: Link) {
+    let mut next = Some(link);
+    let mut backoff = BACKOFF_INITIAL;
+    while !tunnel.stopped() {
+        let link = match next.take() {
+            Some(link) => link,
+            None => {
+                tunnel.set_status(TunnelStatus::Connecting, None);
+                let info = tunnel.info();
+                match Link::open(pool, &tunnel.remote, info.mode, info.remote_port) {
+                    Ok(link) => {
+                        tunnel.stats.reconnects.fetch_add(1, Ordering::Relaxed);
+                        link
+                    }
+                    Err(e) => {
+                        tunnel.set_status(TunnelStatus::Down, Some(e));
+                        tunnel.pause(backoff);
+                        backoff = next_backoff(backoff);
+                        continue;
+                    }
+                }
+            }
+        };
+
+        let began = Instant::now();
+        match forward(tunnel, listener, link) {
+            Ok(()) => return,
+            Err(e) => {
+                eprintln!("SSH tunnel {} lost its session: {}", tunnel.info().id, e);
+                tunnel.set_status(TunnelStatus::Down, Some(e));
+                // A session that stayed up for a while starts the backoff over
+                if began.elapsed() >= BACKOFF_MAX {
+                    backoff = BACKOFF_INITIAL;
+                }
+                tunnel.pause(backoff);
+                backoff = next_backoff(backoff);
+            }
+        }
+    }
+}
+
+/// Add a connection to the poller and to `connections` under the next free key. No
+/// interest is registered yet; the event loop sets it from `wanted_interest`.
+fn track(poller: &Poller, connections: &mut HashMap<usize, Connection>, next_key: &mut usize, conn: Connection) -> Option<usize> {
+    let key = *next_key;
+    *next_key += 1;
+    unsafe { poller.add_with_mode(&conn.stream, Event::none(key), PollMode::Level) }.ok()?;
+    connections.insert(key, conn);
+    Some(key)
+}
+
+/// Serve the tunnel over `link` until it is stopped or the session fails. Local tunnels
+/// take connections from `listener`; reverse tunnels take channels from the server.
+fn forward(tunnel: &Tunnel, listener: Option<&TcpListener>, link: Link) -> Result<(), String> {
+    let Link { sess, mut remote_listener, .. } = link;
+    let poller = &tunnel.poller;
+    let TunnelInfo { local_port, remote_port, .. } = tunnel.info();
+    sess.set_keepalive(true, KEEPALIVE_SECS);
+
+    // An idle session channel; nothing runs on it. Reading it makes libssh2 take in
+    // whatever arrived on the socket (keepalive replies, window updates) even when no
+    // forwarded connection is open to read it, so the socket never stays readable.
+    let mut control = sess.channel_session().map_err(|e| format!("Failed to open SSH channel: {}", e))?;
+    sess.set_blocking(false);
+
+    unsafe {
+        if let Some(listener) = listener {
+            listener.set_nonblocking(true).map_err(|e| e.to_string())?;
+            poller.add_with_mode(listener, Event::readable(LISTENER), PollMode::Level).map_err(|e| e.to_string())?;
+        }
+        poller.add_with_mode(&sess, Event::readable(SESSION), PollMode::Level).map_err(|e| e.to_string())?;
+    }
+
+    let mut connections: HashMap<usize, Connection> = HashMap::new();
+    let mut opening: VecDeque<usize> = VecDeque::new();
+    let mut next_key = FIRST_CONNECTION;
+    let mut session_writable = false;
+    let mut events = Events::new();
+    let mut buf = vec![0; READ_CHUNK];
+    let mut last_heard = Instant::now();
+    tunnel.set_status(TunnelStatus::Up, None);
+
+    let result = loop {
+        if tunnel.stopped() {
+            break Ok(());
+        }
+
+        // New browser connections queue for a channel
+        let mut lost = None;
+        while let Some(listener) = listener {
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    if stream.set_nonblocking(true).is_err() {
+                        continue;
+                    }
+                    if let Some(key) = track(poller, &mut connections, &mut next_key, Connection::new(stream)) {
+                        opening.push_back(key);
+                        tunnel.stats.connections_total.fetch_add(1, Ordering::Relaxed);
+                    }
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == io::ErrorKind::ConnectionAborted => continue,
+                Err(e) => {
+                    lost = Some(format!("Tunnel listener failed: {}", e));
+                    break;
+                }
+            }
+        }
+
+        // Channels the server accepted on a reverse tunnel each get a connection to the local port
+        while let Some(remote_listener) = remote_listener.as_mut() {
+            match remote_listener.accept() {
+                Ok(mut channel) => {
+                    match TcpStream::connect(("127.0.0.1", local_port)) {
+                        Ok(stream) if stream.set_nonblocking(true).is_ok() => {
+                            let mut conn = Connection::new(stream);
+                            conn.channel = Some(channel);
+                            if track(poller, &mut connections, &mut next_key, conn).is_some() {
+                                tunnel.stats.connections_total.fetch_add(1, Ordering::Relaxed);
+                            }
+                        }
+                        // Nothing is listening locally (gateway not running); refuse this one
+                        _ => {
+                            let _ = channel.close();
+                        }
+                    }
+                }
+                Err(e) if would_block(&e) => break,
+                Err(e) => {
+                    lost = Some(format!("Failed to accept a forwarded connection: {}", e));
+                    break;
+                }
+            }
+        }
+
+        // libssh2 opens one channel at a time per session; an open that would block is
+        // resumed by calling again with the same arguments.
+        while lost.is_none() {
+            let Some(&key) = opening.front() else { break };
+            match sess.channel_direct_tcpip("127.0.0.1", remote_port, None) {
+                Ok(channel) => {
+                    opening.pop_front();
+                    if let Some(conn) = connections.get_mut(&key) {
+                        conn.channel = Some(channel);
+                    }
+                }
+                Err(e) if would_block(&e) => break,
+                Err(e) => {
+                    // The server refusing the forward (gateway down) only fails this connection
+                    opening.pop_front();
+                    if let Some(conn) = connections.remove(&key) {
+                        close_connection(poller, conn);
+                    }
+                    if e.code() != ErrorCode::Session(LIBSSH2_ERROR_CHANNEL_FAILURE) {
+                        lost = Some(format!("Failed to open SSH channel: {}", e));
+                        break;
+                    }
+                }
+            }
+        }
+        if let Some(e) = lost {
+            break Err(e);
+        }
+
+        let mut finished = Vec::new();
+        for (&key, conn) in connections.iter_mut() {
+            match conn.pump(&mut buf, &tunnel.stats) {
+                Ok(false) => {
+                    let wanted = conn.wanted_interest();
+                    if wanted != conn.interest {
+                        let event = Event::new(key, wanted.0, wanted.1);
+                        if poller.modify_with_mode(&conn.stream, event, PollMode::Level).is_ok() {
+                            conn.interest = wanted;
+                        }
+                    }
+                }
+                Ok(true) | Err(_) => finished.push(key),
+            }
+        }
+        for key in finished {
+            if let Some(conn) = connections.remove(&key) {
+                close_connection(poller, conn);
+            }
+        }
+
+        match control.read(&mut buf) {
+            Ok(0) if control.eof() => break Err("The SSH session was closed by the server".to_string()),
+            Err(e) if e.kind() != io::ErrorKind::WouldBlock => break Err(format!("SSH session failed: {}", e)),
+            _ => {}
+        }
+
+        tunnel.stats.connections_active.store(connections.len() as u64, Ordering::Relaxed);
+
+        let silent = last_heard.elapsed();
+        if silent > Duration::from_secs((KEEPALIVE_SECS * DEAD_AFTER).into()) {
+            break Err(format!("No response from the SSH server for {} seconds", silent.as_secs()));
+        } else if silent > Duration::from_secs((KEEPALIVE_SECS * DEGRADED_AFTER).into()) {
+            tunnel.set_status(TunnelStatus::Degraded, None);
+        } else {
+            tunnel.set_status(TunnelStatus::Up, None);
+        }
+
+        let keepalive_in = match sess.keepalive_send() {
+            Ok(secs) => secs.max(1),
+            Err(e) if would_block(&e) => 1,
+            Err(e) => break Err(format!("SSH keepalive failed: {}", e)),
+        };
+
+        // Only wait for the SSH socket to drain when libssh2 is stuck writing to it;
+        // a writable socket would otherwise wake the loop constantly.
+        let wants_writable = matches!(sess.block_directions(), ssh2::BlockDirections::Outbound | ssh2::BlockDirections::Both);
+        if wants_writable != session_writable {
+            let event = Event::new(SESSION, true, wants_writable);
+            if poller.modify_with_mode(session_source(&sess), event, PollMode::Level).is_ok() {
+                session_writable = wants_writable;
+            }
+        }
+
+        events.clear();
+        if let Err(e) = poller.wait(&mut events, Some(Duration::from_secs(keepalive_in.into()))) {
+            if e.kind() != io::ErrorKind::Interrupted {
+                break Err(format!("Tunnel poller failed: {}", e));
+            }
+        }
+        if events.iter().any(|event| event.key == SESSION && event.readable) {
+            last_heard = Instant::now();
+        }
+    };
+
+    for (_, conn) in connections.drain() {
+        close_connection(poller, conn);
+    }
+    tunnel.stats.connections_active.store(0, Ordering::Relaxed);
+    if let Some(listener) = listener {
+        let _ = poller.delete(listener);
+    }
+    let _ = poller.delete(session_source(&sess));
+    let _ = control.close();
+    result
+}
+
+/// Carry `stream` over `channel` on the calling thread until the local side closes or
+/// the channel ends. Jump hosts use this to put a `direct-tcpip` channel behind a socket
+/// libssh2 can run a session over; `sess` is the hop session the channel belongs to.
+pub(crate) fn bridge(sess: Session, channel: Channel, stream: TcpStream) -> Result<(), String> {
+    let poller = Poller::new().map_err(|e| format!("Failed to create bridge poller: {}", e))?;
+    stream.set_nonblocking(true).map_err(|e| e.to_string())?;
+    sess.set_blocking(false);
+    let mut conn = Connection::new(stream);
+    conn.channel = Some(channel);
+    unsafe {
+        poller.add_with_mode(&conn.stream, Event::none(FIRST_CONNECTION), PollMode::Level).map_err(|e| e.to_string())?;
+        poller.add_with_mode(&sess, Event::none(SESSION), PollMode::Level).map_err(|e| e.to_string())?;
+    }
+
+    let stats = TunnelStats::default();
+    let mut session_interest = (false, false);
+    let mut events = Events::new();
+    let mut buf = vec![0; READ_CHUNK];
+    let result = loop {
+        match conn.pump(&mut buf, &stats) {
+            // The session on top closed its socket, so nothing more will be read from the channel
+            Ok(false) if !conn.eof_sent => {}
+            Ok(_) => break Ok(()),
+            Err(e) => break Err(e.to_string()),
+        }
+
+        let wanted = conn.wanted_interest();
+        if wanted != conn.interest {
+            let event = Event::new(FIRST_CONNECTION, wanted.0, wanted.1);
+            if poller.modify_with_mode(&conn.stream, event, PollMode::Level).is_ok() {
+                conn.interest = wanted;
+            }
+        }
+
+        // Wait for the SSH socket only while there is room for what the channel brings,
+        // or something is waiting to go out; a readable socket left unread would wake the
+        // loop constantly.
+        let blocked = sess.block_directions();
+        let wanted = (
+            (!conn.remote_eof && conn.to_local.len() < BUFFER_LIMIT)
+                || !conn.to_remote.is_empty()
+                || matches!(blocked, ssh2::BlockDirections::Inbound | ssh2::BlockDirections::Both),
+            matches!(blocked, ssh2::BlockDirections::Outbound | ssh2::BlockDirections::Both),
+        );
+        if wanted != session_interest {
+            let event = Event::new(SESSION, wanted.0, wanted.1);
+            if poller.modify_with_mode(session_source(&sess), event, PollMode::Level).is_ok() {
+                session_interest = wanted;
+            }
+        }
+
+        events.clear();
+        if let Err(e) = poller.wait(&mut events, None) {
+            if e.kind() != io::ErrorKind::Interrupted {
+                break Err(format!("Bridge poller failed: {}", e));
+            }
+        }
+    };
+
+    let _ = poller.delete(session_source(&sess));
+    close_connection(&poller, conn);
+    result
+}
+
+fn close_connection(poller: &Poller, mut conn: Connection) {
+    let _ = poller.delete(&conn.stream);
+    if let Some(channel) = conn.channel.as_mut() {
+        let _ = channel.close();
+    }
+    let _ = conn.stream.shutdown(Shutdown::Both);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tunnel_registry_tracks_tunnels_by_host() {
+        use TunnelMode::{Local, Reverse};
+        let registry = TunnelRegistry::new();
+        let pool = SshPool::new();
+        let (status_tx, status_rx) = std::sync::mpsc::channel();
+        registry.on_status(move |info| {
+            let _ = status_tx.send((info.id.clone(), info.status));
+        });
+        let a: RemoteInfo = serde_json::from_str(r#"{ "ip": "10.0.0.5", "user": "ubuntu" }"#).unwrap();
+        let b: RemoteInfo = serde_json::from_str(r#"{ "ip": "10.0.0.6", "user": "ubuntu" }"#).unwrap();
+        // Serve until stopped, standing in for the SSH event loop
+        let idle = |tunnel: &Tunnel| {
+            while !tunnel.stopped() {
+                std::thread::sleep(std::time::Duration::from_millis(10));
+            }
+        };
+
+        let first = registry.launch(a.clone(), Local, 18789, 18789, idle).unwrap();
+        let second = registry.launch(b.clone(), Local, 18790, 18789, idle).unwrap();
+        // Publishing the local gateway on host a does not count as a tunnel to a's dashboard
+        let reverse = registry.launch(a.clone(), Reverse, 18789, 8080, idle).unwrap();
+        // Both tunnels run side by side on different local ports
+        assert_ne!(first.id, second.id);
+        assert_ne!(first.local_port, second.local_port);
+        assert_eq!(first.status, TunnelStatus::Connecting);
+        assert_eq!(registry.list().len(), 3);
+
+        // Starting the same host and port again reuses the running tunnel without connecting
+        assert_eq!(registry.start(&pool, a.clone(), None, 18789).unwrap().id, first.id);
+        assert_eq!(registry.find(&b).map(|t| t.id), Some(second.id.clone()));
+        // Reverse tunnels are reused by remote port just like local ones
+        assert_eq!(registry.start_reverse(&pool, a.clone(), 18789, 8080).unwrap().id, reverse.id);
+        assert_ne!(registry.find(&a).map(|t| t.id), Some(reverse.id.clone()));
+
+        // An explicit local port that is taken is an error rather than a silent fallback
+        let taken = bind_local(None, 0).unwrap();
+        let taken_port = taken.local_addr().unwrap().port();
+        assert!(registry.start(&pool, b.clone(), Some(taken_port), 8080).is_err());
+
+        registry.stop(&first.id).unwrap();
+        assert!(registry.find(&a).is_none());
+        assert!(registry.stop(&first.id).is_err());
+        // The tunnel thread reports that it stopped
+        let event = status_rx.recv_timeout(std::time::Duration::from_secs(5)).unwrap();
+        assert_eq!(event, (first.id.clone(), TunnelStatus::Stopped));
+
+        // Reconnect delays double up to a ceiling
+        let mut delays = vec![std::time::Duration::from_secs(1)];
+        for _ in 0..7 {
+            delays.push(next_backoff(*delays.last().unwrap()));
+        }
+        let secs: Vec<u64> = delays.iter().map(|d| d.as_secs()).collect();
+        assert_eq!(secs, vec![1, 2, 4, 8, 16, 32, 60, 60]);
+        registry.stop_all();
+        assert!(registry.list().is_empty());
+    }
+}
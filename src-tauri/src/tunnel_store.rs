@@ -0,0 +1,204 @@
+// Tunnel definitions saved in the app data directory, so tunnels can come back
+// after Clawnetes restarts. Only what it takes to reconnect with a key file or
+// the SSH agent is written: passwords, key passphrases and sudo passwords never
+// leave memory.
+
+use crate::ssh_pool::SshPool;
+use crate::tunnel::{TunnelInfo, TunnelMode, TunnelRegistry};
+use crate::{RemoteInfo, DEFAULT_GATEWAY_PORT};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+const STORE_FILE: &str = "tunnels.json";
+
+/// The parts of a `RemoteInfo` that are safe to keep on disk.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SavedHost {
+    ip: String,
+    user: String,
+    #[serde(default)]
+    port: Option<u16>,
+    #[serde(default)]
+    private_key_path: Option<String>,
+    #[serde(default)]
+    jump_hosts: Vec<SavedHost>,
+}
+
+impl SavedHost {
+    pub(crate) fn from_remote(remote: &RemoteInfo) -> SavedHost {
+        SavedHost {
+            ip: remote.ip.clone(),
+            user: remote.user.clone(),
+            port: remote.port,
+            private_key_path: remote.private_key_path.clone(),
+            jump_hosts: remote.jump_hosts.iter().flatten().map(SavedHost::from_remote).collect(),
+        }
+    }
+
+    pub(crate) fn to_remote(&self) -> RemoteInfo {
+        RemoteInfo {
+            ip: self.ip.clone(),
+            user: self.user.clone(),
+            port: self.port,
+            password: None,
+            private_key_path: self.private_key_path.clone(),
+            key_passphrase: None,
+            jump_hosts: if self.jump_hosts.is_empty() {
+                None
+            } else {
+                Some(self.jump_hosts.iter().map(SavedHost::to_remote).collect())
+            },
+            sudo_password: None,
+        }
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SavedTunnel {
+    pub(crate) host: SavedHost,
+    pub(crate) mode: TunnelMode,
+    // Local tunnels: the local port the user asked for, if any.
+    // Reverse tunnels: the local gateway port being published.
+    pub(crate) local_port: Option<u16>,
+    pub(crate) remote_port: u16,
+}
+
+impl SavedTunnel {
+    /// What tells saved tunnels apart: host, user, mode and remote port.
+    fn key(&self) -> (&str, &str, TunnelMode, u16) {
+        (&self.host.ip, &self.host.user, self.mode, self.remote_port)
+    }
+
+    /// Whether this definition is the one behind the running tunnel `info`.
+    fn describes(&self, info: &TunnelInfo) -> bool {
+        self.key() == (info.host.as_str(), info.user.as_str(), info.mode, info.remote_port)
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Default, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SavedTunnels {
+    // Start the saved tunnels when the app starts. Off until the user opts in.
+    #[serde(default)]
+    pub(crate) auto_restore: bool,
+    #[serde(default)]
+    pub(crate) tunnels: Vec<SavedTunnel>,
+}
+
+pub(crate) struct TunnelStore {
+    // None when the platform has no app data directory; nothing is saved then
+    path: Option<PathBuf>,
+    // Serializes read-modify-write cycles on the file
+    lock: Mutex<()>,
+}
+
+impl TunnelStore {
+    pub(crate) fn new(app_data_dir: Option<PathBuf>) -> TunnelStore {
+        TunnelStore { path: app_data_dir.map(|dir| dir.join(STORE_FILE)), lock: Mutex::new(()) }
+    }
+
+    /// The saved tunnels. A missing or unreadable file counts as none saved.
+    pub(crate) fn load(&self) -> SavedTunnels {
+        self.path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    fn update(&self, change: impl FnOnce(&mut SavedTunnels)) -> Result<(), String> {
+        let Some(path) = &self.path else { return Ok(()) };
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let mut saved = self.load();
+        change(&mut saved);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        let json = serde_json::to_string_pretty(&saved).map_err(|e| e.to_string())?;
+        fs::write(path, json).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    /// Save `tunnel`, replacing an earlier definition of the same tunnel.
+    pub(crate) fn remember(&self, tunnel: SavedTunnel) -> Result<(), String> {
+        self.update(|saved| {
+            saved.tunnels.retain(|t| t.key() != tunnel.key());
+            saved.tunnels.push(tunnel);
+        })
+    }
+
+    /// Drop the definition behind the running tunnel `info`.
+    pub(crate) fn forget(&self, info: &TunnelInfo) -> Result<(), String> {
+        self.update(|saved| saved.tunnels.retain(|t| !t.describes(info)))
+    }
+
+    pub(crate) fn forget_all(&self) -> Result<(), String> {
+        self.update(|saved| saved.tunnels.clear())
+    }
+
+    pub(crate) fn set_auto_restore(&self, enabled: bool) -> Result<(), String> {
+        self.update(|saved| saved.auto_restore = enabled)
+    }
+}
+
+/// Start the saved tunnels again. Tunnels that cannot connect without a password
+/// (or whose host is unreachable) are reported and skipped; their definitions are kept.
+pub(crate) fn restore(pool: &SshPool, registry: &TunnelRegistry, tunnels: Vec<SavedTunnel>) {
+    for saved in tunnels {
+        let remote = saved.host.to_remote();
+        let started = match saved.mode {
+            TunnelMode::Local => registry.start(pool, remote, saved.local_port, saved.remote_port),
+            TunnelMode::Reverse => {
+                registry.start_reverse(pool, remote, saved.local_port.unwrap_or(DEFAULT_GATEWAY_PORT), saved.remote_port)
+            }
+        };
+        if let Err(e) = started {
+            eprintln!("Failed to restore SSH tunnel to {}@{}: {}", saved.host.user, saved.host.ip, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn test_tunnel_store_keeps_definitions_without_secrets() {
+        let dir = TempDir::new("tunnels");
+        let store = TunnelStore::new(Some(dir.path().to_path_buf()));
+        assert_eq!(store.load(), Default::default());
+
+        let remote: RemoteInfo = serde_json::from_str(
+            r#"{ "ip": "10.0.0.5", "user": "ubuntu", "password": "hunter2", "privateKeyPath": "/home/me/.ssh/id_ed25519",
+                 "keyPassphrase": "secret-phrase", "sudoPassword": "sudo-pw",
+                 "jumpHosts": [{ "ip": "bastion", "user": "jump", "password": "hop-pw" }] }"#,
+        )
+        .unwrap();
+        let tunnel = SavedTunnel { host: SavedHost::from_remote(&remote), mode: TunnelMode::Local, local_port: None, remote_port: 18789 };
+        store.remember(tunnel.clone()).unwrap();
+        // Saving the same tunnel again replaces it
+        store.remember(SavedTunnel { local_port: Some(9000), ..tunnel.clone() }).unwrap();
+        store.set_auto_restore(true).unwrap();
+
+        let text = fs::read_to_string(dir.join("tunnels.json")).unwrap();
+        for secret in ["hunter2", "secret-phrase", "sudo-pw", "hop-pw"] {
+            assert!(!text.contains(secret), "{} was written to disk", secret);
+        }
+        let saved = store.load();
+        assert!(saved.auto_restore);
+        assert_eq!(saved.tunnels.len(), 1);
+        assert_eq!(saved.tunnels[0].local_port, Some(9000));
+        let restored = saved.tunnels[0].host.to_remote();
+        assert_eq!(restored.private_key_path.as_deref(), Some("/home/me/.ssh/id_ed25519"));
+        assert_eq!(restored.jump_hosts.as_ref().map(|hops| hops[0].ip.as_str()), Some("bastion"));
+        assert!(restored.password.is_none() && restored.key_passphrase.is_none() && restored.sudo_password.is_none());
+
+        store.forget_all().unwrap();
+        assert!(store.load().tunnels.is_empty());
+        // Without an app data directory nothing is saved, and nothing fails
+        assert!(TunnelStore::new(None).remember(tunnel).is_ok());
+    }
+}
@@ -1,7 +1,8 @@
 import { useState, useEffect, useRef } from "react";
 import { invoke } from "@tauri-apps/api/tauri";
 import { open } from "@tauri-apps/api/shell";
-import { open as openDialog } from "@tauri-apps/api/dialog";
+import { open as openDialog, save as saveDialog } from "@tauri-apps/api/dialog";
+import { listen } from "@tauri-apps/api/event";
 import "./App.css";
 import { PERSONA_TEMPLATES } from "./presets/personaTemplates";
 import { MODELS_BY_PROVIDER, DEFAULT_MODELS, PROVIDER_LOGOS, EMOJI_OPTIONS, SKILL_ICONS } from "./presets/modelsByProvider";
@@ -10,7 +11,7 @@ import { AGENT_TYPE_PRESETS } from "./presets/agentPresets";
 import { BUSINESS_FUNCTION_PRESETS } from "./presets/businessFunctionPresets";
 import { updateIdentityField, updateSoulMission } from "./utils/markdownHelpers";
 import Dropdown from "./components/Dropdown";
-import type { AgentTypeId, AgentConfigData, BusinessFunctionId, CronJobConfig } from "./types";
+import type { AgentTypeId, AgentConfigData, BusinessFunctionId, CronJobConfig, HostKeyInfo, SshConfigHost, InstallStepEvent, InstallLogEvent, ServiceHealth, PreflightReport, TunnelInfo, ConfigPlan, PlannedFile, SnapshotInfo, BundleManifest, ImportReport } from "./types";
 
 function App() {
   const handleAdvancedTransition = async () => {
@@ -36,7 +37,14 @@ function App() {
   const [remotePrivateKeyPath, setRemotePrivateKeyPath] = useState("");
   const [sshStatus, setSshStatus] = useState<"idle" | "checking" | "requesting_password" | "success" | "error">("idle");
   const [sshError, setSshError] = useState("");
+  const [preflight, setPreflight] = useState<PreflightReport | null>(null);
   const [tunnelActive, setTunnelActive] = useState(false);
+  const [tunnel, setTunnel] = useState<TunnelInfo | null>(null);
+  const tunnelIdRef = useRef<string | null>(null);
+  const [autoRestoreTunnels, setAutoRestoreTunnels] = useState(false);
+  const [configPlan, setConfigPlan] = useState<ConfigPlan | null>(null);
+  useEffect(() => { tunnelIdRef.current = tunnel?.id ?? null; }, [tunnel]);
+  const [sshConfigHosts, setSshConfigHosts] = useState<SshConfigHost[]>([]);
 
   const [checks, setChecks] = useState({ node: false, docker: false, openclaw: false });
   const [loading, setLoading] = useState(false);
@@ -64,6 +72,7 @@ function App() {
   const [maintenanceStatus, setMaintenanceStatus] = useState("");
   const [selectedMaint, setSelectedMaint] = useState<string>("repair");
   const [maintCompleted, setMaintCompleted] = useState(false);
+  const [snapshots, setSnapshots] = useState<SnapshotInfo[]>([]);
 
   // Service Keys State
   const [serviceKeys, setServiceKeys] = useState<Record<string, string>>({});
@@ -240,6 +249,77 @@ function App() {
     }
   }, [step]);
 
+  // Stream installer progress from the backend into the log view
+  useEffect(() => {
+    const unlistenStep = listen<InstallStepEvent>("install-step", ({ payload }) => {
+      if (payload.status === "started") {
+        setProgress(`${payload.step}...`);
+        setLogs(prev => prev + `\n==> ${payload.step}`);
+      } else if (payload.status === "failed") {
+        setLogs(prev => prev + `\n${payload.step} failed: ${payload.error ?? "unknown error"}`);
+      }
+    });
+    const unlistenLog = listen<InstallLogEvent>("install-log", ({ payload }) => {
+      setLogs(prev => prev + "\n" + payload.line);
+    });
+    return () => {
+      unlistenStep.then(f => f());
+      unlistenLog.then(f => f());
+    };
+  }, []);
+
+  // Follow the tunnel supervisor as it reconnects after sleep or network changes
+  useEffect(() => {
+    const unlisten = listen<TunnelInfo>("tunnel-status", ({ payload }) => {
+      // Tunnels restored at startup show up here before the UI has started one
+      const restored = tunnelIdRef.current === null && payload.mode === "local" && payload.status !== "stopped";
+      if (tunnelIdRef.current !== payload.id && !restored) return;
+      tunnelIdRef.current = payload.id;
+      if (payload.status === "stopped") {
+        setTunnel(null);
+        setTunnelActive(false);
+        return;
+      }
+      setTunnel(payload);
+      setTunnelActive(true);
+      if (payload.status === "down") {
+        setMaintenanceStatus(`⚠️ SSH tunnel lost its connection, reconnecting... ${payload.lastError ?? ""}`.trim());
+      } else if (payload.status === "degraded") {
+        setMaintenanceStatus("⚠️ SSH tunnel is not getting responses from the server.");
+      } else if (payload.status === "up" && payload.reconnects > 0) {
+        setMaintenanceStatus(`✅ SSH tunnel reconnected on localhost:${payload.localPort}.`);
+      }
+    });
+    invoke<boolean>("get_tunnel_auto_restore").then(setAutoRestoreTunnels).catch(() => {});
+    // Tunnels restored at startup may have come up before this listener existed
+    invoke<TunnelInfo[]>("list_ssh_tunnels")
+      .then((tunnels) => {
+        const running = tunnels.find(t => t.mode === "local" && t.status !== "stopped");
+        if (!running || tunnelIdRef.current !== null) return;
+        tunnelIdRef.current = running.id;
+        setTunnel(running);
+        setTunnelActive(true);
+      })
+      .catch((e) => console.error("Failed to list SSH tunnels:", e));
+    return () => {
+      unlisten.then(f => f());
+    };
+  }, []);
+
+  // Offer aliases from ~/.ssh/config in the remote form
+  useEffect(() => {
+    if (targetEnvironment !== "cloud") return;
+    invoke<SshConfigHost[]>("list_ssh_config_hosts")
+      .then(setSshConfigHosts)
+      .catch((e) => console.error("Failed to read SSH config:", e));
+  }, [targetEnvironment]);
+
+  function handleRemoteHostChange(value: string) {
+    setRemoteIp(value);
+    const entry = sshConfigHosts.find((h) => h.alias === value);
+    if (entry?.user && !remoteUser) setRemoteUser(entry.user);
+  }
+
   // Update default auth method when provider changes
   useEffect(() => {
     if (provider === "anthropic") setAuthMethod("token");
@@ -388,15 +468,35 @@ function App() {
     setSshError("");
 
     try {
-      // Changed to use object parameter to match backend
-      const checkPromise = invoke("test_ssh_connection", {
-        remote: {
-          ip: remoteIp,
-          user: remoteUser,
-          password: remotePassword || null,
-          privateKeyPath: remotePrivateKeyPath || null
+      const remote = {
+        ip: remoteIp,
+        user: remoteUser,
+        password: remotePassword || null,
+        privateKeyPath: remotePrivateKeyPath || null
+      };
+
+      // Verify the host keys before sending any credentials. Each call returns the next
+      // host on the way (jump hosts first, then the server) that is not trusted yet.
+      for (;;) {
+        const hostKey: HostKeyInfo = await invoke("get_ssh_host_key", { remote });
+        const hostName = hostKey.port === 22 ? hostKey.host : `${hostKey.host}:${hostKey.port}`;
+        if (hostKey.status === "changed") {
+          throw new Error(`Host key for ${hostName} has changed (${hostKey.fingerprint}). Refusing to connect.`);
         }
-      });
+        if (hostKey.status === "trusted") {
+          break;
+        }
+        const trusted = window.confirm(
+          `First connection to ${hostName}.\n\n${hostKey.key_type} key fingerprint:\n${hostKey.fingerprint}\n\nTrust this host?`
+        );
+        if (!trusted) {
+          throw new Error("Host key was not trusted");
+        }
+        await invoke("trust_ssh_host_key", { remote, fingerprint: hostKey.fingerprint });
+      }
+
+      // Changed to use object parameter to match backend
+      const checkPromise = invoke("test_ssh_connection", { remote });
 
       // Timeout after 15 seconds
       const timeoutPromise = new Promise((_, reject) => 
@@ -407,6 +507,11 @@ function App() {
 
       setSshStatus("success");
       setSshError("");
+
+      setPreflight(null);
+      invoke<PreflightReport>("run_remote_preflight", { remote, gatewayPort })
+        .then(setPreflight)
+        .catch((e) => console.warn("Remote preflight failed:", e));
     } catch (e) {
       setSshStatus("idle"); // Reset to idle on error so user can retry
       const friendlyError = formatSshError(String(e));
@@ -517,6 +622,7 @@ Managed by Clawnetes.`,
         agents_md: a.agents_md || null,
       })) : null,
       preserve_state: isPaired,
+      merge_config: false,
       agent_type: initial.agent_type || "custom",
       tools_md: initial.tools_md || null,
       agents_md: initial.agents_md || null,
@@ -583,6 +689,7 @@ Managed by Clawnetes.`,
           agents_md: a.agentsMd || null,
         })) : null,
         preserve_state: isPaired,
+        merge_config: false,
         // New preset fields
         agent_type: agentType,
         tools_md: usePresetFields && toolsMd ? toolsMd : null,
@@ -594,6 +701,33 @@ Managed by Clawnetes.`,
     };
   }
 
+  // Dry run of the install: what would be written and run, without touching the target
+  async function handlePreviewPlan() {
+    const remoteConfig = targetEnvironment === "cloud" ? {
+      ip: remoteIp,
+      user: remoteUser,
+      password: remotePassword || null,
+      privateKeyPath: remotePrivateKeyPath || null
+    } : null;
+    const configPayload = constructConfigPayload();
+    configPayload.merge_config = !!initialConfigRef.current;
+    try {
+      setConfigPlan(await invoke<ConfigPlan>("plan_configuration", { remote: remoteConfig, config: configPayload }));
+    } catch (e) {
+      setConfigPlan(null);
+      window.alert(`Could not preview the changes: ${e}`);
+    }
+  }
+
+  function formatPlan(plan: ConfigPlan): string {
+    const parts = plan.files.filter(f => f.status !== "unchanged").map(f => f.diff);
+    if (plan.merge && plan.merge.untouched.length > 0) {
+      parts.unshift(`Kept as is: ${plan.merge.untouched.join(", ")}\n`);
+    }
+    parts.push("Commands:\n" + plan.commands.map(c => `$ ${c}`).join("\n"));
+    return parts.join("\n");
+  }
+
   async function handleInstall() {
     setLoading(true);
     setError(false);
@@ -636,6 +770,8 @@ Managed by Clawnetes.`,
              }, 500);
              return;
         }
+        // Reconfiguring keeps whatever was added to openclaw.json by hand
+        configPayload.merge_config = true;
     }
 
     try {
@@ -649,6 +785,19 @@ Managed by Clawnetes.`,
           config: configPayload
         });
 
+        // User services stop at logout unless lingering is enabled; offer to turn it on
+        try {
+          const health: ServiceHealth = await invoke("get_remote_service_health", { remote: remoteConfig });
+          if (health.linger === false && window.confirm(
+            "The gateway will stop when you log out of the server because systemd lingering is disabled for this user.\n\nEnable lingering so it keeps running?"
+          )) {
+            await invoke("enable_remote_linger", { remote: remoteConfig });
+          }
+        } catch (e) {
+          console.warn("Remote service health check failed:", e);
+          setLogs(prev => prev + `\nWarning: ${e}`);
+        }
+
         // Install skills on remote server
         for (const skill of selectedSkills) {
           setProgress(`Installing skill on remote: ${skill}...`);
@@ -666,15 +815,8 @@ Managed by Clawnetes.`,
 
         setProgress("Establishing SSH tunnel...");
         setLogs("Creating SSH tunnel to remote gateway...");
-        try {
-          await invoke("start_ssh_tunnel", { remote: remoteConfig });
-        } catch (e: any) {
-          if (String(e).includes("SSH tunnel is already running")) {
-            setLogs(prev => prev + "\nTunnel already active.");
-          } else {
-            throw e;
-          }
-        }
+        // Returns the running tunnel if this host already has one
+        setTunnel(await invoke<TunnelInfo>("start_ssh_tunnel", { remote: remoteConfig }));
         setTunnelActive(true);
 
         // Verify tunnel is working with HTTP connectivity test
@@ -730,9 +872,10 @@ Managed by Clawnetes.`,
         setProgress("Configuring agent...");
         setLogs("Configuring...");
 
-        await invoke("configure_agent", {
+        const configured: string = await invoke("configure_agent", {
           config: configPayload
         });
+        setLogs(configured);
 
         for (const skill of selectedSkills) {
           setProgress(`Installing skill: ${skill}...`);
@@ -820,6 +963,29 @@ Managed by Clawnetes.`,
           ? await invoke("run_remote_security_audit_fix", { remote: remoteConfig })
           : await invoke("run_security_audit_fix");
         setMaintenanceStatus(`✅ Security Audit completed successfully.`);
+      } else if (action === "export") {
+        const path = await saveDialog({ title: "Export Agent Bundle", defaultPath: "openclaw-agent.tar.gz" });
+        if (!path) {
+          setMaintenanceStatus("");
+          setLoading(false);
+          return;
+        }
+        const manifest: BundleManifest = await invoke("export_agent_bundle", { remote: remoteConfig, path });
+        res = `Saved ${manifest.files.length} files to ${path}\n` +
+          (manifest.strippedSecrets.length > 0 ? `Left out (enter again after importing):\n${manifest.strippedSecrets.join("\n")}\n` : "");
+        setMaintenanceStatus(`✅ Agent bundle exported.`);
+      } else if (action === "import") {
+        const path = await openDialog({ title: "Import Agent Bundle", directory: false, multiple: false, filters: [{ name: "Agent Bundle", extensions: ["gz"] }] });
+        if (!path || typeof path !== "string") {
+          setMaintenanceStatus("");
+          setLoading(false);
+          return;
+        }
+        const report: ImportReport = await invoke("import_agent_bundle", { remote: remoteConfig, path });
+        res = `Imported ${report.files.length} files.\n` +
+          (report.snapshot ? `The previous state was saved as snapshot ${report.snapshot}.\n` : "") +
+          (report.missingSecrets.length > 0 ? `Reconfigure to enter the secrets the bundle left out:\n${report.missingSecrets.join("\n")}\n` : "");
+        setMaintenanceStatus(`✅ Agent bundle imported. Restart the gateway to load it.`);
       } else if (action === "update") {
         if (remoteConfig) {
            res = await invoke("update_remote_openclaw", { remote: remoteConfig });
@@ -845,6 +1011,38 @@ Managed by Clawnetes.`,
     setLoading(false);
   }
 
+  async function handleSnapshotAction(action: "list" | "diff" | "restore", id?: string) {
+    setLoading(true);
+    const remoteConfig = targetEnvironment === "cloud" && sshStatus === "success" ? {
+      ip: remoteIp,
+      user: remoteUser,
+      password: remotePassword || null,
+      privateKeyPath: remotePrivateKeyPath || null
+    } : null;
+    try {
+      if (action === "list") {
+        const list: SnapshotInfo[] = await invoke("list_snapshots", { remote: remoteConfig });
+        setSnapshots(list);
+        setMaintenanceStatus(list.length > 0 ? "" : "No snapshots yet: one is taken before each change Clawnetes makes.");
+      } else if (action === "diff") {
+        const files: PlannedFile[] = await invoke("diff_snapshot", { remote: remoteConfig, id });
+        const changed = files.filter(f => f.status !== "unchanged");
+        setLogs(changed.length > 0 ? changed.map(f => f.diff).join("\n") : "Nothing to restore: the files match this snapshot.");
+        setMaintenanceStatus(`Changes restoring ${id} would make:`);
+      } else {
+        const res: string = await invoke("restore_snapshot", { remote: remoteConfig, id });
+        setLogs(res);
+        setMaintenanceStatus(`✅ Snapshot restored.`);
+        setSnapshots(await invoke("list_snapshots", { remote: remoteConfig }));
+        setMaintCompleted(true);
+      }
+    } catch (e) {
+      setLogs(`Error: ${e}`);
+      setMaintenanceStatus(`❌ Snapshot ${action} failed.`);
+    }
+    setLoading(false);
+  }
+
   async function loadExistingConfig() {
     setLoading(true);
     setMaintenanceStatus("Loading existing configuration...");
@@ -950,7 +1148,8 @@ Managed by Clawnetes.`,
     setLoading(true);
     if (tunnelActive) {
       try {
-        await invoke("stop_ssh_tunnel");
+        await invoke("stop_ssh_tunnel", { id: tunnel?.id ?? null });
+        setTunnel(null);
         setTunnelActive(false);
         setMaintenanceStatus("✅ SSH Tunnel disconnected.");
       } catch (e) {
@@ -965,9 +1164,10 @@ Managed by Clawnetes.`,
           password: remotePassword || null, 
           privateKeyPath: remotePrivateKeyPath || null 
         };
-        await invoke("start_ssh_tunnel", { remote });
+        const info = await invoke<TunnelInfo>("start_ssh_tunnel", { remote });
+        setTunnel(info);
         setTunnelActive(true);
-        setMaintenanceStatus("✅ SSH Tunnel established on port 18789.");
+        setMaintenanceStatus(`✅ SSH Tunnel established on localhost:${info.localPort}.`);
       } catch (e) {
         setMaintenanceStatus(`❌ Failed to establish tunnel: ${e}`);
       }
@@ -1037,7 +1237,8 @@ Managed by Clawnetes.`,
                     if (tunnelActive) {
                       // Stop tunnel
                       try {
-                        await invoke("stop_ssh_tunnel");
+                        await invoke("stop_ssh_tunnel", { id: tunnel?.id ?? null });
+                        setTunnel(null);
                         setTunnelActive(false);
                         setMaintenanceStatus("✅ SSH tunnel stopped.");
                       } catch (e) {
@@ -1067,14 +1268,14 @@ Managed by Clawnetes.`,
 
                         // Establish tunnel
                         setMaintenanceStatus("Establishing SSH tunnel...");
-                        await invoke("start_ssh_tunnel", {
+                        setTunnel(await invoke<TunnelInfo>("start_ssh_tunnel", {
                           remote: {
                             ip: remoteIp,
                             user: remoteUser,
                             password: remotePassword || null,
                             privateKeyPath: remotePrivateKeyPath || null
                           }
-                        });
+                        }));
                         setTunnelActive(true);
                         setMaintenanceStatus("✅ SSH tunnel established successfully. Dashboard is now accessible.");
                       } catch (e) {
@@ -1090,6 +1291,25 @@ Managed by Clawnetes.`,
               )}
             </div>
 
+            {targetEnvironment === "cloud" && (
+              <label style={{display: "flex", alignItems: "center", gap: "0.5rem", marginBottom: "1.5rem"}}>
+                <input
+                  type="checkbox"
+                  checked={autoRestoreTunnels}
+                  onChange={async (e) => {
+                    const enabled = e.target.checked;
+                    try {
+                      await invoke("set_tunnel_auto_restore", { enabled });
+                      setAutoRestoreTunnels(enabled);
+                    } catch (err) {
+                      setMaintenanceStatus(`❌ Failed to save tunnel setting: ${err}`);
+                    }
+                  }}
+                />
+                Reconnect the SSH tunnel when Clawnetes starts (key or SSH agent logins only)
+              </label>
+            )}
+
             {/* Maintenance Options */}
             <h3 style={{marginBottom: "1rem"}}>Maintenance Options</h3>
             <div className="mode-card-container" style={{gridTemplateColumns: "1fr", gap: "1rem"}}>
@@ -1133,9 +1353,61 @@ Managed by Clawnetes.`,
                 <h3 style={selectedMaint === "uninstall" ? {color: "var(--error)"} : {}}>🗑 Uninstall Completely</h3>
                 <p>Remove the OpenClaw CLI and all {targetEnvironment === "local" ? "local" : "remote"} configuration/data files.</p>
               </div>
+
+              <div
+                className={`mode-card ${selectedMaint === "export" ? "active" : ""}`}
+                onClick={() => !loading && setSelectedMaint("export")}
+              >
+                <h3>📦 Export Agent Bundle</h3>
+                <p>Save the agent's configuration, workspaces and custom skills to a file, to set it up on another machine. Secrets are left out.</p>
+              </div>
+
+              <div
+                className={`mode-card ${selectedMaint === "import" ? "active" : ""}`}
+                onClick={() => !loading && setSelectedMaint("import")}
+              >
+                <h3>📥 Import Agent Bundle</h3>
+                <p>Set up the agent from an exported bundle on this {targetEnvironment === "local" ? "machine" : "server"}. The current state is snapshotted first.</p>
+              </div>
+
+              <div
+                className={`mode-card ${selectedMaint === "rollback" ? "active" : ""}`}
+                onClick={() => {
+                  if (loading) return;
+                  setSelectedMaint("rollback");
+                  handleSnapshotAction("list");
+                }}
+              >
+                <h3>⏪ Roll Back</h3>
+                <p>Restore ~/.openclaw from a snapshot taken before an earlier reconfigure, repair, audit fix or uninstall.</p>
+              </div>
             </div>
 
-            {!loading && (
+            {selectedMaint === "rollback" && snapshots.length > 0 && (
+              <div className="mode-card-container" style={{gridTemplateColumns: "1fr", gap: "0.5rem", marginTop: "1rem"}}>
+                {snapshots.map(s => (
+                  <div key={s.id} className="mode-card" style={{display: "flex", alignItems: "center", gap: "10px"}}>
+                    <p style={{flex: 1, margin: 0}}>
+                      {new Date(s.createdAt * 1000).toLocaleString()} — before {s.reason} ({Math.ceil(s.size / 1024)} KB)
+                    </p>
+                    <button className="secondary" disabled={loading} onClick={() => handleSnapshotAction("diff", s.id)}>Preview</button>
+                    <button
+                      className="primary"
+                      disabled={loading}
+                      onClick={() => {
+                        if (confirm("Replace the current configuration and workspaces with this snapshot? The current state is snapshotted first.")) {
+                          handleSnapshotAction("restore", s.id);
+                        }
+                      }}
+                    >
+                      Restore
+                    </button>
+                  </div>
+                ))}
+              </div>
+            )}
+
+            {!loading && selectedMaint !== "rollback" && (
               <div className="button-group" style={{gap: "10px", marginTop: "1.5rem"}}>
                 <button
                   className="primary"
@@ -1221,10 +1493,18 @@ Managed by Clawnetes.`,
                 <div className="form-group">
                   <label>Server IP Address</label>
                   <input
-                    placeholder="192.168.1.100"
+                    placeholder="192.168.1.100 or SSH config alias"
                     value={remoteIp}
-                    onChange={(e) => setRemoteIp(e.target.value)}
+                    onChange={(e) => handleRemoteHostChange(e.target.value)}
+                    list="ssh-config-hosts"
                   />
+                  <datalist id="ssh-config-hosts">
+                    {sshConfigHosts.map((h) => (
+                      <option key={h.alias} value={h.alias}>
+                        {h.hostname ? `${h.user ? h.user + "@" : ""}${h.hostname}` : ""}
+                      </option>
+                    ))}
+                  </datalist>
                 </div>
                 <div className="form-group">
                   <label>SSH Username</label>
@@ -1290,6 +1570,19 @@ Managed by Clawnetes.`,
                   </div>
                 )}
 
+                {sshStatus === "success" && preflight && (
+                  <div style={{marginTop: "1rem", padding: "0.75rem", borderRadius: "8px", border: "1px solid var(--border)"}}>
+                    <strong style={{color: "var(--text)"}}>Server check</strong>
+                    <ul style={{listStyle: "none", padding: 0, margin: "0.5rem 0 0"}}>
+                      {preflight.checks.map((c) => (
+                        <li key={c.id} style={{color: "var(--text)", marginBottom: "0.25rem"}}>
+                          {c.status === "pass" ? "✅" : c.status === "warn" ? "⚠️" : "❌"} <strong>{c.label}:</strong> {c.detail}
+                        </li>
+                      ))}
+                    </ul>
+                  </div>
+                )}
+
                 {sshError && (
                   <div className="error" style={{marginTop: "1rem", padding: "0.75rem", backgroundColor: "rgba(239, 68, 68, 0.1)", borderRadius: "8px", border: "1px solid rgba(239, 68, 68, 0.3)"}}>
                     <strong style={{color: "rgb(239, 68, 68)"}}>❌ Error:</strong> <span style={{color: "var(--text)"}}>{sshError}</span>
@@ -3182,6 +3475,12 @@ case 16:
                </p>
             </div>
 
+            {configPlan && !loading && (
+              <div className="logs-container" style={{marginBottom: "2rem"}}>
+                <pre>{formatPlan(configPlan)}</pre>
+              </div>
+            )}
+
             {(loading || error) && (
               <div className="progress-container" style={{marginBottom: "2rem"}}>
                 {loading && (
@@ -3193,14 +3492,24 @@ case 16:
                 <div className="logs-container">
                   <pre>{logs}</pre>
                 </div>
+                {loading && (
+                  <button className="secondary" style={{marginTop: "1rem"}} onClick={() => invoke("cancel_operation")}>
+                    Cancel
+                  </button>
+                )}
               </div>
             )}
 
             <div className="button-group">
               {hasChanges ? (
-                <button className="primary" onClick={handleInstall} disabled={loading}>
-                  {loading ? (initialConfigRef.current ? "Updating..." : "Installing...") : (initialConfigRef.current ? "Update Configuration" : "Finish Setup")}
-                </button>
+                <>
+                  <button className="primary" onClick={handleInstall} disabled={loading}>
+                    {loading ? (initialConfigRef.current ? "Updating..." : "Installing...") : (initialConfigRef.current ? "Update Configuration" : "Finish Setup")}
+                  </button>
+                  {initialConfigRef.current && (
+                    <button className="secondary" onClick={handlePreviewPlan} disabled={loading}>Preview Changes</button>
+                  )}
+                </>
               ) : (
                 <button className="primary" onClick={() => setStep(17)}>
                   Next
@@ -3354,7 +3663,7 @@ case 16:
                 </h4>
                 <p style={{fontSize: "0.85rem", color: "var(--text-muted)", margin: 0}}>
                   {tunnelActive
-                    ? `Remote gateway (${remoteIp}:18789) is forwarded to localhost:18789`
+                    ? `Remote gateway (${remoteIp}:${tunnel?.remotePort ?? gatewayPort}) is forwarded to localhost:${tunnel?.localPort ?? gatewayPort}`
                     : "SSH tunnel is not active"}
                 </p>
                 {tunnelActive ? (
@@ -3363,7 +3672,8 @@ case 16:
                     style={{marginTop: "1rem", width: "100%"}}
                     onClick={async () => {
                       try {
-                        await invoke("stop_ssh_tunnel");
+                        await invoke("stop_ssh_tunnel", { id: tunnel?.id ?? null });
+                        setTunnel(null);
                         setTunnelActive(false);
                       } catch (e) {
                         console.error("Failed to stop tunnel:", e);
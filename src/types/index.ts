@@ -67,10 +67,133 @@ export interface ServiceKeyConfig {
 }
 
 export interface RemoteConfig {
-  ip: string;
+  ip: string; // hostname, IPv4, or IPv6 (bare or [bracketed]), optionally with :port
   user: string;
+  port?: number | null; // SSH port, defaults to 22
   password: string | null;
   privateKeyPath: string | null;
+  keyPassphrase?: string | null; // kept in memory only, never persisted
+  jumpHosts?: RemoteConfig[] | null; // bastions to tunnel through, first entry dialed directly
+  sudoPassword?: string | null; // fed to `sudo -S` on stdin only, never persisted
+}
+
+// Returned by get_ssh_host_key; confirm with trust_ssh_host_key before connecting
+export interface HostKeyInfo {
+  host: string;
+  port: number;
+  key_type: string;
+  fingerprint: string;
+  status: "trusted" | "unknown" | "changed";
+}
+
+export interface InstallStepEvent {
+  step: string;
+  status: "started" | "finished" | "failed";
+  error: string | null;
+}
+
+export interface InstallLogEvent {
+  step: string;
+  stream: "stdout" | "stderr";
+  line: string;
+}
+
+export interface SshConfigHost {
+  alias: string;
+  hostname: string | null;
+  user: string | null;
+  port: number | null;
+  identityFile: string | null;
+  proxyJump: string | null;
+}
+
+// Returned by get_remote_service_health
+export interface ServiceHealth {
+  manager: "systemd" | "launchd" | "supervised";
+  linger: boolean | null; // null when not applicable or logind cannot tell
+  running: boolean;
+  activeState: string;
+  subState: string;
+  logs: string[];
+}
+
+// Returned by start_ssh_tunnel and list_ssh_tunnels
+// Emitted as "tunnel-status" whenever a tunnel's status changes
+export type TunnelStatus = "connecting" | "up" | "degraded" | "down" | "stopped";
+
+export interface TunnelInfo {
+  id: string;
+  host: string;
+  user: string;
+  mode: "local" | "reverse"; // reverse publishes localPort on remotePort of the host
+  localPort: number;
+  remotePort: number;
+  startedAt: number; // seconds since the Unix epoch
+  status: TunnelStatus;
+  lastError?: string | null; // why the session was last lost
+  bytesIn: number; // gateway to browser
+  bytesOut: number; // browser to gateway
+  connectionsTotal: number;
+  connectionsActive: number;
+  reconnects: number;
+}
+
+// Returned by run_remote_preflight
+export type CheckStatus = "pass" | "warn" | "fail";
+
+export interface PreflightCheck {
+  id: string;
+  label: string;
+  status: CheckStatus;
+  detail: string;
+}
+
+export interface PreflightReport {
+  status: CheckStatus; // worst status among the checks
+  checks: PreflightCheck[];
+}
+
+// Returned by plan_configuration and diff_snapshot; secrets are masked throughout
+export interface PlannedFile {
+  path: string;
+  status: "added" | "modified" | "removed" | "unchanged";
+  diff: string; // unified diff against the current file, empty when unchanged
+}
+
+export interface MergeReport {
+  changed: string[];
+  added: string[];
+  removed: string[];
+  untouched: string[]; // keys Clawnetes does not manage, kept as they were
+}
+
+export interface ConfigPlan {
+  files: PlannedFile[];
+  commands: string[]; // in the order they would run
+  merge: MergeReport | null;
+}
+
+// A tar.gz of ~/.openclaw taken before Clawnetes changed it
+export interface SnapshotInfo {
+  id: string;
+  createdAt: number; // seconds since the Unix epoch
+  reason: "configure" | "update" | "skill-install" | "skill-create" | "workspace-edit" | "uninstall" | "doctor-repair" | "security-fix" | "import" | "restore";
+  size: number; // bytes
+}
+
+// Returned by export_agent_bundle; bundles never carry secrets
+export interface BundleManifest {
+  version: number;
+  createdAt: number; // seconds since the Unix epoch
+  sourceHome: string;
+  files: string[]; // relative to ~/.openclaw
+  strippedSecrets: string[]; // "<file>: <dotted key path>"
+}
+
+export interface ImportReport {
+  files: string[];
+  missingSecrets: string[]; // to enter again, e.g. by reconfiguring
+  snapshot: string | null; // taken before importing, to roll back to
 }
 
 // Agent Type Presets
@@ -170,6 +293,8 @@ export interface ConfigPayload {
   soul_md: string | null;
   agents: AgentPayloadData[] | null;
   preserve_state: boolean;
+  // Merge into the existing openclaw.json, keeping keys added by hand
+  merge_config?: boolean;
   // New fields for presets
   agent_type?: string;
   tools_md?: string | null;